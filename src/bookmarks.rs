@@ -0,0 +1,104 @@
+use log::error;
+use std::collections::BTreeMap;
+use visions_of_mandelbrot::{DoubleDouble, ViewParams};
+
+// Named views kept across sessions, in a JSON file on the desktop and in localStorage on the web.
+#[derive(Default)]
+pub(crate) struct Bookmarks {
+    views: BTreeMap<String, ViewParams<DoubleDouble>>,
+    // Kept for the session only, changes leave the saved bookmarks alone.
+    session: bool,
+}
+
+impl Bookmarks {
+    // File name on the desktop, key in localStorage on the web.
+    pub const STORAGE_NAME: &'static str = "bookmarks.json";
+
+    // Starts out empty if nothing was saved yet or the saved bookmarks can't be read.
+    pub(crate) fn load() -> Self {
+        let json = match Bookmarks::read() {
+            Some(json) => json,
+            None => return Bookmarks::default(),
+        };
+
+        match serde_json::from_str(&json) {
+            Ok(views) => Bookmarks { views, session: false },
+            Err(e) => {
+                error!("failed to parse {}: {}", Bookmarks::STORAGE_NAME, e);
+                Bookmarks::default()
+            }
+        }
+    }
+
+    // Bookmarks that aren't saved, like the screenshots of a gallery.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn session(views: BTreeMap<String, ViewParams<DoubleDouble>>) -> Self {
+        Bookmarks { views, session: true }
+    }
+
+    // Adds or replaces a bookmark and saves them all.
+    pub(crate) fn add(&mut self, name: &str, view: ViewParams<DoubleDouble>) {
+        self.views.insert(name.to_string(), view);
+        self.save();
+    }
+
+    pub(crate) fn remove(&mut self, name: &str) -> Option<ViewParams<DoubleDouble>> {
+        let view = self.views.remove(name)?;
+        self.save();
+        Some(view)
+    }
+
+    // Bookmark names in sorted order.
+    pub(crate) fn list(&self) -> impl Iterator<Item = &str> {
+        self.views.keys().map(String::as_str)
+    }
+
+    pub(crate) fn jump(&self, name: &str) -> Option<ViewParams<DoubleDouble>> {
+        self.views.get(name).copied()
+    }
+
+    fn save(&self) {
+        if self.session {
+            return;
+        }
+        let json = serde_json::to_string_pretty(&self.views).expect("views always serialize");
+        Bookmarks::write(&json);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn read() -> Option<String> {
+        match std::fs::read_to_string(Bookmarks::STORAGE_NAME) {
+            Ok(json) => Some(json),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                error!("failed to read {}: {}", Bookmarks::STORAGE_NAME, e);
+                None
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn write(json: &str) {
+        if let Err(e) = std::fs::write(Bookmarks::STORAGE_NAME, json) {
+            error!("failed to save {}: {}", Bookmarks::STORAGE_NAME, e);
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn storage() -> Option<web_sys::Storage> {
+        web_sys::window()?.local_storage().ok()?
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn read() -> Option<String> {
+        Bookmarks::storage()?.get_item(Bookmarks::STORAGE_NAME).ok()?
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn write(json: &str) {
+        let saved = Bookmarks::storage().map(|storage| storage.set_item(Bookmarks::STORAGE_NAME, json).is_ok());
+        if saved != Some(true) {
+            error!("failed to save {} to localStorage", Bookmarks::STORAGE_NAME);
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use crate::double_double::DoubleDouble;
+use crate::mandelbrot::{Component, Formula, FractalType, MandelbrotGenerator};
+use rand::Rng;
+
+// Density of escaping trajectories across the view, accumulated a batch of random samples at a time. Unlike the
+// escape-time frame the picture is never finished, it just gets less noisy the longer it runs.
+//
+// Each layer only counts the trajectories that escape within its own iteration cap. A single layer is the Buddhabrot,
+// three layers with different caps mapped to red, green and blue are the Nebulabrot. In Julia mode c stays put and the
+// random samples are starting points instead.
+pub(crate) struct Buddhabrot {
+    width: usize,
+    height: usize,
+    // Everything the trajectories depend on, without the pixels. A change to any of it starts the accumulation over.
+    generator: MandelbrotGenerator<DoubleDouble>,
+    caps: Vec<usize>,
+    // Visits per pixel for each layer, flat in row-major order.
+    densities: Vec<Vec<u32>>,
+    samples: u64,
+    // Reused between samples to hold the trajectory being traced.
+    trajectory: Vec<(f64, f64)>,
+}
+
+// By hand since the generator isn't Clone, a copy of its settings is all that's kept of it anyway.
+impl Clone for Buddhabrot {
+    fn clone(&self) -> Self {
+        Buddhabrot {
+            width: self.width,
+            height: self.height,
+            generator: self.generator.without_pixels(),
+            caps: self.caps.clone(),
+            densities: self.densities.clone(),
+            samples: self.samples,
+            trajectory: Vec::new(),
+        }
+    }
+}
+
+impl Buddhabrot {
+    // Random c values, or starting points in Julia mode, are drawn from the square of this half-width around the
+    // origin, which holds the whole set.
+    pub const SAMPLE_RADIUS: f64 = 2.0;
+
+    pub(crate) fn new(generator: &MandelbrotGenerator<DoubleDouble>, caps: &[usize]) -> Self {
+        let (width, height) = generator.dimensions();
+        Buddhabrot {
+            width,
+            height,
+            generator: generator.without_pixels(),
+            caps: caps.to_vec(),
+            densities: vec![vec![0; width * height]; caps.len()],
+            samples: 0,
+            trajectory: Vec::new(),
+        }
+    }
+
+    // Whether the density was accumulated with these caps for the generator's current view and settings.
+    pub(crate) fn matches(&self, generator: &MandelbrotGenerator<DoubleDouble>, caps: &[usize]) -> bool {
+        self.caps == caps
+            && (self.width, self.height) == generator.dimensions()
+            && self.generator.view() == generator.view()
+            && self.generator.formula() == generator.formula()
+            && self.generator.morph() == generator.morph()
+            && self.generator.power() == generator.power()
+            && self.generator.bailout() == generator.bailout()
+            && self.generator.fractal_type() == generator.fractal_type()
+            && (generator.fractal_type() == FractalType::Mandelbrot || self.generator.julia_c() == generator.julia_c())
+    }
+
+    pub(crate) fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    pub(crate) fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    // Shoots `samples` random points and adds the trajectory of each one that escapes to the layers whose cap it
+    // escaped within. Points in the main cardioid and period-2 bulb never escape, so they're rejected without
+    // iterating.
+    pub(crate) fn accumulate(&mut self, samples: usize) {
+        let generator = &self.generator;
+        let mut rng = rand::thread_rng();
+        let max_iterations = self.caps.iter().copied().max().unwrap_or(0);
+        let julia = generator.fractal_type() == FractalType::Julia;
+        let classic =
+            !julia && generator.formula() == Formula::Mandelbrot && generator.morph().is_none() && generator.power() == 2;
+        let bailout = generator.bailout();
+
+        for _ in 0..samples {
+            let re = rng.gen_range(-Buddhabrot::SAMPLE_RADIUS..Buddhabrot::SAMPLE_RADIUS);
+            let im = rng.gen_range(-Buddhabrot::SAMPLE_RADIUS..Buddhabrot::SAMPLE_RADIUS);
+            self.samples += 1;
+
+            if classic && Component::classify(re, im).is_some() {
+                continue;
+            }
+
+            // Trace the orbit up to the cap, the escaped point itself is outside the set's radius and isn't plotted
+            self.trajectory.clear();
+            let ((cx, cy), (mut x, mut y)) = if julia { (generator.julia_c(), (re, im)) } else { ((re, im), (0.0, 0.0)) };
+            let mut escaped = false;
+            while self.trajectory.len() < max_iterations {
+                (x, y) = generator.step(x, y, x * x, y * y, cx, cy);
+                if x * x + y * y > bailout {
+                    escaped = true;
+                    break;
+                }
+                self.trajectory.push((x, y));
+            }
+
+            if !escaped {
+                continue;
+            }
+
+            for &point in &self.trajectory {
+                let (px, py) = generator.complex_to_pixel(point);
+                if px < 0.0 || py < 0.0 || px >= self.width as f64 || py >= self.height as f64 {
+                    continue;
+                }
+
+                let index = py as usize * self.width + px as usize;
+                for (density, &cap) in self.densities.iter_mut().zip(&self.caps) {
+                    if self.trajectory.len() < cap {
+                        density[index] += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    // Writes the layers into an RGBA frame as the brightness of red, green and blue in turn, a single layer fills all
+    // three. There has to be at least one. Each layer is scaled to its own peak, and the square root keeps the faint
+    // outer trajectories visible next to the heavily visited core.
+    pub(crate) fn draw(&self, frame: &mut [u8]) {
+        let peaks: Vec<f64> = self.densities
+            .iter()
+            .map(|density| density.iter().copied().max().unwrap_or(0).max(1) as f64)
+            .collect();
+
+        for (index, pixel) in frame.chunks_exact_mut(4).enumerate() {
+            for (channel, value) in pixel[..3].iter_mut().enumerate() {
+                let layer = channel.min(self.densities.len() - 1);
+                let brightness = (self.densities[layer][index] as f64 / peaks[layer]).sqrt();
+                *value = (brightness * 255.0).round() as u8;
+            }
+            pixel[3] = 0xff;
+        }
+    }
+}
@@ -0,0 +1,73 @@
+// Sharing locations through the system clipboard, as the text `MandelbrotRenderer::location` writes.
+
+use log::{info, warn};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// The browser only hands clipboard text over asynchronously, so reads land in `pasted` on both targets and the event
+// loop picks them up with `take_pasted`.
+#[derive(Default)]
+pub(crate) struct Clipboard {
+    pasted: Rc<RefCell<Option<String>>>,
+}
+
+impl Clipboard {
+    pub(crate) fn copy(&self, text: &str) {
+        #[cfg(not(target_arch = "wasm32"))]
+        match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text)) {
+            Ok(()) => info!("copied {}", text),
+            Err(e) => warn!("couldn't copy to the clipboard: {}", e),
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            let text = text.to_string();
+            wasm_bindgen_futures::spawn_local(async move {
+                match call_clipboard("writeText", Some(&text)).await {
+                    Ok(_) => info!("copied {}", text),
+                    Err(e) => warn!("couldn't copy to the clipboard: {:?}", e),
+                }
+            });
+        }
+    }
+
+    // Starts reading the clipboard, the text shows up in `take_pasted` once it's there.
+    pub(crate) fn request_paste(&self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+            Ok(text) => *self.pasted.borrow_mut() = Some(text),
+            Err(e) => warn!("couldn't read the clipboard: {}", e),
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            let pasted = Rc::clone(&self.pasted);
+            wasm_bindgen_futures::spawn_local(async move {
+                match call_clipboard("readText", None).await {
+                    Ok(text) => *pasted.borrow_mut() = text.as_string(),
+                    Err(e) => warn!("couldn't read the clipboard: {:?}", e),
+                }
+            });
+        }
+    }
+
+    pub(crate) fn take_pasted(&self) -> Option<String> {
+        self.pasted.borrow_mut().take()
+    }
+}
+
+// Calls a method of `navigator.clipboard` and waits for its promise. Goes through reflection because web-sys only has
+// typed clipboard bindings behind its unstable APIs.
+#[cfg(target_arch = "wasm32")]
+async fn call_clipboard(method: &str, text: Option<&str>) -> Result<wasm_bindgen::JsValue, wasm_bindgen::JsValue> {
+    use wasm_bindgen::JsCast;
+
+    let navigator = web_sys::window().ok_or("couldn't access window")?.navigator();
+    let clipboard = js_sys::Reflect::get(&navigator, &"clipboard".into())?;
+    let function = js_sys::Reflect::get(&clipboard, &method.into())?.dyn_into::<js_sys::Function>()?;
+    let promise = match text {
+        Some(text) => function.call1(&clipboard, &text.into())?,
+        None => function.call0(&clipboard)?,
+    };
+    wasm_bindgen_futures::JsFuture::from(js_sys::Promise::from(promise)).await
+}
@@ -0,0 +1,144 @@
+// Startup preferences read from an optional TOML file.
+
+use crate::keybindings::KeyBindings;
+use log::{error, warn};
+use serde::Deserialize;
+use std::fs;
+use std::io::ErrorKind;
+use visions_of_mandelbrot::PaletteKind;
+
+// Looked up in the working directory.
+pub(crate) const CONFIG_FILE: &str = "visions.toml";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub(crate) enum PaletteChoice {
+    Random,
+    Builtin(PaletteKind),
+}
+
+impl TryFrom<String> for PaletteChoice {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        match name.as_str() {
+            "random" => Ok(PaletteChoice::Random),
+            "rainbow" => Ok(PaletteChoice::Builtin(PaletteKind::Rainbow)),
+            "grayscale" => Ok(PaletteChoice::Builtin(PaletteKind::Grayscale)),
+            "fire" => Ok(PaletteChoice::Builtin(PaletteKind::Fire)),
+            "ocean" => Ok(PaletteChoice::Builtin(PaletteKind::Ocean)),
+            "classic" => Ok(PaletteChoice::Builtin(PaletteKind::Classic)),
+            _ => Err(format!("unknown palette '{}'", name)),
+        }
+    }
+}
+
+// Every key is optional, anything left out keeps the built-in default.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Config {
+    pub(crate) width: Option<u32>,
+    pub(crate) height: Option<u32>,
+    pub(crate) palette: Option<PaletteChoice>,
+    pub(crate) zoom_factor: Option<f64>,
+    // Palette periods per second while cycling the palette.
+    pub(crate) palette_cycle_speed: Option<f32>,
+    // Iteration caps of the red, green and blue Nebulabrot layers.
+    pub(crate) nebulabrot_caps: Option<[usize; 3]>,
+    pub(crate) x_min: Option<f64>,
+    pub(crate) x_max: Option<f64>,
+    pub(crate) y_min: Option<f64>,
+    pub(crate) y_max: Option<f64>,
+    // Read entry by entry in `key_bindings`, so a misspelled action or key name doesn't cost the rest of the file.
+    pub(crate) keys: Option<toml::Value>,
+}
+
+impl Config {
+    // A missing file is the normal case and stays quiet, a malformed one is logged and ignored.
+    pub(crate) fn load() -> Config {
+        let text = match fs::read_to_string(CONFIG_FILE) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Config::default(),
+            Err(e) => {
+                error!("failed to read {}: {}", CONFIG_FILE, e);
+                return Config::default();
+            }
+        };
+
+        toml::from_str(&text).unwrap_or_else(|e| {
+            error!("failed to parse {}: {}", CONFIG_FILE, e);
+            Config::default()
+        })
+    }
+    // The `[keys]` table over the defaults. Entries that don't name an action and a key are logged and skipped, the
+    // others still apply.
+    pub(crate) fn key_bindings(&self) -> KeyBindings {
+        let table = match &self.keys {
+            None => return KeyBindings::default(),
+            Some(toml::Value::Table(table)) => table,
+            Some(_) => {
+                warn!("ignoring keys in {}, expected a table of actions", CONFIG_FILE);
+                return KeyBindings::default();
+            }
+        };
+
+        let valid: toml::value::Table = table
+            .iter()
+            .filter(|(action, key)| {
+                let entry = toml::value::Table::from_iter([(action.to_string(), (*key).clone())]);
+                match toml::Value::Table(entry).try_into::<KeyBindings>() {
+                    Ok(_) => true,
+                    Err(e) => {
+                        warn!("ignoring key binding '{}' in {}: {}", action, CONFIG_FILE, e);
+                        false
+                    }
+                }
+            })
+            .map(|(action, key)| (action.clone(), key.clone()))
+            .collect();
+        let keys: KeyBindings = toml::Value::Table(valid).try_into().unwrap_or_default();
+        for (key, first, second) in keys.conflicts() {
+            warn!("'{}' and '{}' are both bound to {} in {}", first, second, key, CONFIG_FILE);
+        }
+        keys
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit::event::VirtualKeyCode;
+
+    fn key_bindings(text: &str) -> KeyBindings {
+        toml::from_str::<Config>(text).unwrap().key_bindings()
+    }
+
+    #[test]
+    fn unknown_actions_are_skipped_and_the_rest_apply() {
+        let keys = key_bindings("[keys]\nzoom_sideways = \"Q\"\nzoom_in = \"I\"\n");
+        assert_eq!(
+            keys,
+            KeyBindings {
+                zoom_in: VirtualKeyCode::I,
+                ..KeyBindings::default()
+            }
+        );
+    }
+
+    #[test]
+    fn unknown_key_names_are_skipped() {
+        let keys = key_bindings("[keys]\nzoom_in = \"Fourteen\"\nzoom_out = \"O\"\n");
+        assert_eq!(
+            keys,
+            KeyBindings {
+                zoom_out: VirtualKeyCode::O,
+                ..KeyBindings::default()
+            }
+        );
+    }
+
+    #[test]
+    fn keys_that_are_not_a_table_keep_the_defaults() {
+        assert_eq!(key_bindings("keys = \"P\"\n"), KeyBindings::default());
+    }
+}
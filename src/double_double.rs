@@ -0,0 +1,679 @@
+// Double-double arithmetic, an unevaluated sum of two f64s for roughly 106 bits of mantissa.
+//
+// Based on the algorithms in Hida, Li and Bailey, "Library for Double-Double and Quad-Double Arithmetic". The basic
+// arithmetic, square root and rounding are exact to double-double precision. Everything transcendental goes through the
+// f64 high part and only carries f64 precision, which is fine for the escape loop since it never needs them on the
+// orbit itself.
+
+use num_traits::{Float, Num, NumCast, One, ToPrimitive, Zero};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use std::num::FpCategory;
+use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+
+/// A float with twice the precision of `f64` and the same range, for views deeper than `f64` can resolve.
+///
+/// Always normalized, so `hi` is the value rounded to the nearest `f64` and `lo` the remainder. Serializes as a plain
+/// number when the low part is zero, so views saved in `f64` load unchanged.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "Repr", into = "Repr")]
+pub struct DoubleDouble {
+    hi: f64,
+    lo: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Repr {
+    Single(f64),
+    Pair(f64, f64),
+}
+
+impl From<Repr> for DoubleDouble {
+    fn from(repr: Repr) -> Self {
+        match repr {
+            Repr::Single(value) => DoubleDouble::from_f64(value),
+            Repr::Pair(hi, lo) => DoubleDouble::new(hi, lo),
+        }
+    }
+}
+
+impl From<DoubleDouble> for Repr {
+    fn from(value: DoubleDouble) -> Self {
+        if value.lo == 0.0 {
+            Repr::Single(value.hi)
+        } else {
+            Repr::Pair(value.hi, value.lo)
+        }
+    }
+}
+
+// a + b as a rounded sum and its exact error.
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    let bb = s - a;
+    (s, (a - (s - bb)) + (b - bb))
+}
+
+// Same as `two_sum`, only valid when |a| >= |b|.
+fn quick_two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    (s, b - (s - a))
+}
+
+// Splits a into two halves of 26 bits each, so their products are exact.
+fn split(a: f64) -> (f64, f64) {
+    const SPLITTER: f64 = 134_217_729.0; // 2^27 + 1
+    let t = SPLITTER * a;
+    let hi = t - (t - a);
+    (hi, a - hi)
+}
+
+// a * b as a rounded product and its exact error. Dekker's method rather than `mul_add`, which is a slow library call
+// on targets without a hardware FMA.
+fn two_prod(a: f64, b: f64) -> (f64, f64) {
+    let p = a * b;
+    let (a_hi, a_lo) = split(a);
+    let (b_hi, b_lo) = split(b);
+    (p, ((a_hi * b_hi - p) + a_hi * b_lo + a_lo * b_hi) + a_lo * b_lo)
+}
+
+impl DoubleDouble {
+    /// The sum `hi + lo`, normalized.
+    pub fn new(hi: f64, lo: f64) -> Self {
+        let (hi, lo) = two_sum(hi, lo);
+        DoubleDouble { hi, lo }
+    }
+
+    /// An exact conversion, usable in constants unlike `From<f64>`.
+    pub const fn from_f64(value: f64) -> Self {
+        DoubleDouble { hi: value, lo: 0.0 }
+    }
+
+    /// The value rounded to the nearest `f64`.
+    pub fn hi(self) -> f64 {
+        self.hi
+    }
+
+    /// The part of the value `hi` is missing, at most half an f64 ulp of it.
+    pub fn lo(self) -> f64 {
+        self.lo
+    }
+
+    // Applies an f64 function to the high part, for everything that only needs f64 precision.
+    fn map_hi(self, f: impl FnOnce(f64) -> f64) -> Self {
+        DoubleDouble::from_f64(f(self.hi))
+    }
+}
+
+impl From<f64> for DoubleDouble {
+    fn from(value: f64) -> Self {
+        DoubleDouble::from_f64(value)
+    }
+}
+
+impl fmt::Display for DoubleDouble {
+    // Scientific notation to the 32 significant digits the type holds, trailing zeros dropped, so that
+    // `from_str_radix` reads it back to within a few ulps. Anything that isn't a finite nonzero number prints as f64.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const DIGITS: usize = 32;
+        if !self.is_finite() || self.hi == 0.0 {
+            return fmt::Display::fmt(&self.hi, f);
+        }
+
+        // Scale into [1, 10), the f64 logarithm can be one off right next to a power of ten
+        let ten = DoubleDouble::from_f64(10.0);
+        let mut exponent = self.hi.abs().log10().floor() as i32;
+        let mut value = self.abs() / ten.powi(exponent);
+        if value >= ten {
+            value = value / ten;
+            exponent += 1;
+        } else if value < DoubleDouble::one() {
+            value = value * ten;
+            exponent -= 1;
+        }
+        if !value.is_finite() {
+            return fmt::Display::fmt(&self.hi, f);
+        }
+
+        let mut digits = String::with_capacity(DIGITS);
+        for _ in 0..DIGITS {
+            let digit = value.floor().hi.clamp(0.0, 9.0);
+            digits.push(char::from(b'0' + digit as u8));
+            value = (value - DoubleDouble::from_f64(digit)) * ten;
+        }
+        let digits = digits.trim_end_matches('0');
+        let sign = if self.hi < 0.0 { "-" } else { "" };
+        match &digits[1..] {
+            "" => write!(f, "{}{}e{}", sign, digits, exponent),
+            fraction => write!(f, "{}{}.{}e{}", sign, &digits[..1], fraction, exponent),
+        }
+    }
+}
+
+impl PartialOrd for DoubleDouble {
+    // Normalized values compare by their high parts first, the low parts only break ties
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match self.hi.partial_cmp(&other.hi)? {
+            Ordering::Equal => self.lo.partial_cmp(&other.lo),
+            ordering => Some(ordering),
+        }
+    }
+}
+
+impl Neg for DoubleDouble {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        DoubleDouble { hi: -self.hi, lo: -self.lo }
+    }
+}
+
+impl Add for DoubleDouble {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        let (s, e) = two_sum(self.hi, other.hi);
+        let (t, f) = two_sum(self.lo, other.lo);
+        let (s, e) = quick_two_sum(s, e + t);
+        let (hi, lo) = quick_two_sum(s, e + f);
+        DoubleDouble { hi, lo }
+    }
+}
+
+impl Sub for DoubleDouble {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self + -other
+    }
+}
+
+impl Mul for DoubleDouble {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let (p, e) = two_prod(self.hi, other.hi);
+        let (hi, lo) = quick_two_sum(p, e + (self.hi * other.lo + self.lo * other.hi));
+        DoubleDouble { hi, lo }
+    }
+}
+
+impl Div for DoubleDouble {
+    type Output = Self;
+
+    // Long division, each quotient digit taken from the high parts and the remainder carried exactly
+    fn div(self, other: Self) -> Self {
+        let q1 = self.hi / other.hi;
+        if !q1.is_finite() {
+            return DoubleDouble::from_f64(q1);
+        }
+
+        let r = self - other * DoubleDouble::from_f64(q1);
+        let q2 = r.hi / other.hi;
+        let r = r - other * DoubleDouble::from_f64(q2);
+        let q3 = r.hi / other.hi;
+
+        let (hi, lo) = quick_two_sum(q1, q2);
+        DoubleDouble { hi, lo } + DoubleDouble::from_f64(q3)
+    }
+}
+
+impl Rem for DoubleDouble {
+    type Output = Self;
+
+    fn rem(self, other: Self) -> Self {
+        self - other * (self / other).trunc()
+    }
+}
+
+impl Zero for DoubleDouble {
+    fn zero() -> Self {
+        DoubleDouble::from_f64(0.0)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.hi == 0.0
+    }
+}
+
+impl One for DoubleDouble {
+    fn one() -> Self {
+        DoubleDouble::from_f64(1.0)
+    }
+}
+
+impl Num for DoubleDouble {
+    type FromStrRadixErr = <f64 as Num>::FromStrRadixErr;
+
+    // Decimal strings are parsed digit by digit to full precision, other radixes only to f64 precision.
+    fn from_str_radix(text: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        let fallback = || f64::from_str_radix(text, radix).map(DoubleDouble::from_f64);
+        if radix != 10 {
+            return fallback();
+        }
+
+        let (mantissa, exponent) = match text.find(['e', 'E']) {
+            Some(index) => match text[index + 1..].parse::<i32>() {
+                Ok(exponent) => (&text[..index], exponent),
+                Err(_) => return fallback(),
+            },
+            None => (text, 0),
+        };
+        let (negative, digits) = match mantissa.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, mantissa.strip_prefix('+').unwrap_or(mantissa)),
+        };
+
+        let ten = DoubleDouble::from_f64(10.0);
+        let mut value = DoubleDouble::zero();
+        let mut fraction_digits = 0;
+        let mut seen_point = false;
+        let mut seen_digit = false;
+        for c in digits.chars() {
+            match c {
+                '.' if !seen_point => seen_point = true,
+                '0'..='9' => {
+                    value = value * ten + DoubleDouble::from_f64(c.to_digit(10).unwrap_or_default() as f64);
+                    seen_digit = true;
+                    if seen_point {
+                        fraction_digits += 1;
+                    }
+                }
+                _ => return fallback(),
+            }
+        }
+        if !seen_digit {
+            return fallback();
+        }
+
+        let scale = exponent - fraction_digits;
+        let value = if scale < 0 { value / ten.powi(-scale) } else { value * ten.powi(scale) };
+        Ok(if negative { -value } else { value })
+    }
+}
+
+impl ToPrimitive for DoubleDouble {
+    fn to_i64(&self) -> Option<i64> {
+        let value = self.trunc();
+        (value.hi as i128 + value.lo as i128).try_into().ok().filter(|_| value.is_finite())
+    }
+
+    fn to_u64(&self) -> Option<u64> {
+        let value = self.trunc();
+        (value.hi as i128 + value.lo as i128).try_into().ok().filter(|_| value.is_finite())
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        Some(self.hi)
+    }
+}
+
+impl NumCast for DoubleDouble {
+    fn from<T: ToPrimitive>(n: T) -> Option<Self> {
+        n.to_f64().map(DoubleDouble::from_f64)
+    }
+}
+
+impl Float for DoubleDouble {
+    fn nan() -> Self {
+        DoubleDouble::from_f64(f64::NAN)
+    }
+
+    fn infinity() -> Self {
+        DoubleDouble::from_f64(f64::INFINITY)
+    }
+
+    fn neg_infinity() -> Self {
+        DoubleDouble::from_f64(f64::NEG_INFINITY)
+    }
+
+    fn neg_zero() -> Self {
+        DoubleDouble::from_f64(-0.0)
+    }
+
+    fn min_value() -> Self {
+        DoubleDouble::from_f64(f64::MIN)
+    }
+
+    fn min_positive_value() -> Self {
+        DoubleDouble::from_f64(f64::MIN_POSITIVE)
+    }
+
+    // 2^-104, the spacing of double-doubles just above one
+    fn epsilon() -> Self {
+        DoubleDouble::from_f64(f64::EPSILON * f64::EPSILON)
+    }
+
+    fn max_value() -> Self {
+        DoubleDouble::from_f64(f64::MAX)
+    }
+
+    fn is_nan(self) -> bool {
+        self.hi.is_nan()
+    }
+
+    fn is_infinite(self) -> bool {
+        self.hi.is_infinite()
+    }
+
+    fn is_finite(self) -> bool {
+        self.hi.is_finite()
+    }
+
+    fn is_normal(self) -> bool {
+        self.hi.is_normal()
+    }
+
+    fn classify(self) -> FpCategory {
+        self.hi.classify()
+    }
+
+    fn floor(self) -> Self {
+        let hi = self.hi.floor();
+        if hi == self.hi {
+            DoubleDouble::new(hi, self.lo.floor())
+        } else {
+            DoubleDouble::from_f64(hi)
+        }
+    }
+
+    fn ceil(self) -> Self {
+        let hi = self.hi.ceil();
+        if hi == self.hi {
+            DoubleDouble::new(hi, self.lo.ceil())
+        } else {
+            DoubleDouble::from_f64(hi)
+        }
+    }
+
+    // Halfway cases round away from zero, like f64
+    fn round(self) -> Self {
+        let half = DoubleDouble::from_f64(0.5);
+        if self.is_sign_negative() {
+            (self - half).ceil()
+        } else {
+            (self + half).floor()
+        }
+    }
+
+    fn trunc(self) -> Self {
+        if self.is_sign_negative() {
+            self.ceil()
+        } else {
+            self.floor()
+        }
+    }
+
+    fn fract(self) -> Self {
+        self - self.trunc()
+    }
+
+    fn abs(self) -> Self {
+        if self.is_sign_negative() {
+            -self
+        } else {
+            self
+        }
+    }
+
+    fn signum(self) -> Self {
+        DoubleDouble::from_f64(self.hi.signum())
+    }
+
+    fn is_sign_positive(self) -> bool {
+        self.hi.is_sign_positive()
+    }
+
+    fn is_sign_negative(self) -> bool {
+        self.hi.is_sign_negative()
+    }
+
+    fn mul_add(self, a: Self, b: Self) -> Self {
+        self * a + b
+    }
+
+    fn recip(self) -> Self {
+        DoubleDouble::one() / self
+    }
+
+    fn powi(self, n: i32) -> Self {
+        let mut base = self;
+        let mut exponent = n.unsigned_abs();
+        let mut result = DoubleDouble::one();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result * base;
+            }
+            base = base * base;
+            exponent >>= 1;
+        }
+
+        if n < 0 {
+            result.recip()
+        } else {
+            result
+        }
+    }
+
+    fn powf(self, n: Self) -> Self {
+        self.map_hi(|hi| hi.powf(n.hi))
+    }
+
+    // One Newton step from the f64 root doubles its precision
+    fn sqrt(self) -> Self {
+        if self.hi <= 0.0 || !self.hi.is_finite() {
+            return DoubleDouble::from_f64(self.hi.sqrt());
+        }
+
+        let x = 1.0 / self.hi.sqrt();
+        let ax = DoubleDouble::from_f64(self.hi * x);
+        let correction = (self - ax * ax).hi * (x * 0.5);
+        ax + DoubleDouble::from_f64(correction)
+    }
+
+    fn exp(self) -> Self {
+        self.map_hi(f64::exp)
+    }
+
+    fn exp2(self) -> Self {
+        self.map_hi(f64::exp2)
+    }
+
+    // ln(hi + lo) = ln(hi) + ln(1 + lo / hi), and the last term is lo / hi to double-double precision
+    fn ln(self) -> Self {
+        DoubleDouble::from_f64(self.hi.ln()) + DoubleDouble::from_f64(self.lo / self.hi)
+    }
+
+    fn log(self, base: Self) -> Self {
+        self.ln() / base.ln()
+    }
+
+    fn log2(self) -> Self {
+        self.ln() / DoubleDouble::from_f64(std::f64::consts::LN_2)
+    }
+
+    fn log10(self) -> Self {
+        self.ln() / DoubleDouble::from_f64(std::f64::consts::LN_10)
+    }
+
+    fn max(self, other: Self) -> Self {
+        if self.is_nan() || other > self {
+            other
+        } else {
+            self
+        }
+    }
+
+    fn min(self, other: Self) -> Self {
+        if self.is_nan() || other < self {
+            other
+        } else {
+            self
+        }
+    }
+
+    fn abs_sub(self, other: Self) -> Self {
+        (self - other).max(DoubleDouble::zero())
+    }
+
+    fn cbrt(self) -> Self {
+        self.map_hi(f64::cbrt)
+    }
+
+    fn hypot(self, other: Self) -> Self {
+        (self * self + other * other).sqrt()
+    }
+
+    fn sin(self) -> Self {
+        self.map_hi(f64::sin)
+    }
+
+    fn cos(self) -> Self {
+        self.map_hi(f64::cos)
+    }
+
+    fn tan(self) -> Self {
+        self.map_hi(f64::tan)
+    }
+
+    fn asin(self) -> Self {
+        self.map_hi(f64::asin)
+    }
+
+    fn acos(self) -> Self {
+        self.map_hi(f64::acos)
+    }
+
+    fn atan(self) -> Self {
+        self.map_hi(f64::atan)
+    }
+
+    fn atan2(self, other: Self) -> Self {
+        self.map_hi(|hi| hi.atan2(other.hi))
+    }
+
+    fn sin_cos(self) -> (Self, Self) {
+        (self.sin(), self.cos())
+    }
+
+    fn exp_m1(self) -> Self {
+        self.map_hi(f64::exp_m1)
+    }
+
+    fn ln_1p(self) -> Self {
+        self.map_hi(f64::ln_1p)
+    }
+
+    fn sinh(self) -> Self {
+        self.map_hi(f64::sinh)
+    }
+
+    fn cosh(self) -> Self {
+        self.map_hi(f64::cosh)
+    }
+
+    fn tanh(self) -> Self {
+        self.map_hi(f64::tanh)
+    }
+
+    fn asinh(self) -> Self {
+        self.map_hi(f64::asinh)
+    }
+
+    fn acosh(self) -> Self {
+        self.map_hi(f64::acosh)
+    }
+
+    fn atanh(self) -> Self {
+        self.map_hi(f64::atanh)
+    }
+
+    // Only the high part fits the f64 layout
+    fn integer_decode(self) -> (u64, i16, i8) {
+        self.hi.integer_decode()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dd(hi: f64, lo: f64) -> DoubleDouble {
+        DoubleDouble::new(hi, lo)
+    }
+
+    #[test]
+    fn addition_keeps_what_f64_rounds_away() {
+        let sum = DoubleDouble::one() + DoubleDouble::from_f64(1e-20);
+        assert_eq!((sum.hi(), sum.lo()), (1.0, 1e-20));
+        assert_eq!(sum - DoubleDouble::one(), DoubleDouble::from_f64(1e-20));
+
+        // Both halves carry, 2^53 + 1 isn't an f64
+        let sum = dd(2f64.powi(53), 0.5) + dd(1.0, 0.5);
+        assert_eq!((sum.hi(), sum.lo()), (2f64.powi(53) + 2.0, 0.0));
+    }
+
+    #[test]
+    fn multiplication_is_exact_where_the_product_fits() {
+        // (1 + 2^-30)(1 - 2^-30) = 1 - 2^-60, which f64 rounds to one
+        let e = 2f64.powi(-30);
+        let product = dd(1.0, 0.0) * dd(1.0 + e, 0.0) * dd(1.0 - e, 0.0);
+        assert_eq!((product.hi(), product.lo()), (1.0, -2f64.powi(-60)));
+
+        // The low parts take part too: (3 + 2^-60)^2 = 9 + 6 * 2^-60 + 2^-120
+        let product = dd(3.0, 2f64.powi(-60)) * dd(3.0, 2f64.powi(-60));
+        assert_eq!((product.hi(), product.lo()), (9.0, 6.0 * 2f64.powi(-60)));
+    }
+
+    #[test]
+    fn division_is_exact_to_double_double_precision() {
+        // (1 - 2^-60) / (1 + 2^-30) = 1 - 2^-30 exactly
+        let e = 2f64.powi(-30);
+        let quotient = dd(1.0, -2f64.powi(-60)) / dd(1.0 + e, 0.0);
+        assert_eq!((quotient.hi(), quotient.lo()), (1.0 - e, 0.0));
+
+        // A third is 1/3 rounded to f64 plus the rest of it, 2^-54 / 3 short of exact
+        let third = DoubleDouble::one() / DoubleDouble::from_f64(3.0);
+        assert_eq!(third.hi(), 1.0 / 3.0);
+        assert!((third.lo() - 2f64.powi(-54) / 3.0).abs() < 1e-32);
+        assert!((third * DoubleDouble::from_f64(3.0) - DoubleDouble::one()).abs() < DoubleDouble::epsilon());
+    }
+
+    #[test]
+    fn display_reads_back_with_from_str_radix() {
+        let values = [
+            DoubleDouble::one() / DoubleDouble::from_f64(3.0),
+            -DoubleDouble::from_f64(2.0).sqrt(),
+            dd(-0.743643887037158, 7.04752191506114e-17),
+            dd(1.2345e-20, -3.1e-37),
+            dd(6.02e23, 1.5e7),
+            DoubleDouble::from_f64(1000.0),
+            DoubleDouble::zero(),
+        ];
+        for value in values {
+            let text = value.to_string();
+            let parsed = DoubleDouble::from_str_radix(&text, 10).unwrap();
+            let error = (parsed - value).abs();
+            assert!(error <= value.abs() * DoubleDouble::from_f64(1e-30), "{} read back as {}", text, parsed);
+        }
+    }
+
+    #[test]
+    fn rounding_looks_at_the_low_part_when_it_points_the_other_way() {
+        let just_under_three = dd(3.0, -1e-20);
+        assert_eq!(just_under_three.floor(), DoubleDouble::from_f64(2.0));
+        assert_eq!(just_under_three.trunc(), DoubleDouble::from_f64(2.0));
+        assert_eq!(just_under_three.ceil(), DoubleDouble::from_f64(3.0));
+
+        let just_above_minus_three = dd(-3.0, 1e-20);
+        assert_eq!(just_above_minus_three.floor(), DoubleDouble::from_f64(-3.0));
+        assert_eq!(just_above_minus_three.trunc(), DoubleDouble::from_f64(-2.0));
+        assert_eq!(just_above_minus_three.ceil(), DoubleDouble::from_f64(-2.0));
+
+        // A low part of the same sign can't cross an integer
+        assert_eq!(dd(3.0, 1e-20).floor(), DoubleDouble::from_f64(3.0));
+        assert_eq!(dd(3.0, 1e-20).trunc(), DoubleDouble::from_f64(3.0));
+    }
+}
@@ -0,0 +1,75 @@
+// Failures that stop the viewer from starting, reported by `main` instead of panicking.
+
+use std::fmt;
+
+#[derive(Debug)]
+pub(crate) enum Error {
+    // The window couldn't be created.
+    Window(winit::error::OsError),
+    // No GPU surface or device for the pixel buffer.
+    Pixels(pixels::Error),
+    // A headless render couldn't be written.
+    #[cfg(not(target_arch = "wasm32"))]
+    Export(std::path::PathBuf, image::ImageError),
+    // The image a headless render is compared against couldn't be read.
+    #[cfg(not(target_arch = "wasm32"))]
+    Reference(std::path::PathBuf, image::ImageError),
+    // A headless render came out different from its reference image, and how.
+    #[cfg(not(target_arch = "wasm32"))]
+    Mismatch(std::path::PathBuf, String),
+    // The page is missing something the canvas needs, or the browser refused a call. JS errors don't implement
+    // `std::error::Error`, so only their description is kept.
+    #[cfg(target_arch = "wasm32")]
+    Web(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Window(e) => write!(f, "couldn't open a window: {}", e),
+            Error::Pixels(e) => write!(f, "couldn't set up the GPU surface: {}", e),
+            #[cfg(not(target_arch = "wasm32"))]
+            Error::Export(path, e) => write!(f, "saving render to {} failed: {}", path.display(), e),
+            #[cfg(not(target_arch = "wasm32"))]
+            Error::Reference(path, e) => write!(f, "couldn't read reference image {}: {}", path.display(), e),
+            #[cfg(not(target_arch = "wasm32"))]
+            Error::Mismatch(path, difference) => write!(f, "render doesn't match {}: {}", path.display(), difference),
+            #[cfg(target_arch = "wasm32")]
+            Error::Web(description) => write!(f, "couldn't attach to the page: {}", description),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Window(e) => Some(e),
+            Error::Pixels(e) => Some(e),
+            #[cfg(not(target_arch = "wasm32"))]
+            Error::Export(_, e) | Error::Reference(_, e) => Some(e),
+            #[cfg(not(target_arch = "wasm32"))]
+            Error::Mismatch(..) => None,
+            #[cfg(target_arch = "wasm32")]
+            Error::Web(_) => None,
+        }
+    }
+}
+
+impl From<winit::error::OsError> for Error {
+    fn from(e: winit::error::OsError) -> Self {
+        Error::Window(e)
+    }
+}
+
+impl From<pixels::Error> for Error {
+    fn from(e: pixels::Error) -> Self {
+        Error::Pixels(e)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl From<wasm_bindgen::JsValue> for Error {
+    fn from(value: wasm_bindgen::JsValue) -> Self {
+        Error::Web(value.as_string().unwrap_or_else(|| format!("{:?}", value)))
+    }
+}
@@ -0,0 +1,77 @@
+use visions_of_mandelbrot::MandelbrotRenderer;
+
+// Automatic "interesting spot finder". Candidates are sampled along a spiral around the view center, each is scored
+// by the local variance of its iteration counts, and the view zooms toward the best one.
+pub(crate) struct AutoExplorer {
+    pub(crate) active: bool,
+    // Half-width in pixels of the square window used for scoring a candidate.
+    pub(crate) scoring_window: usize,
+    // Zoom factor applied on every step.
+    pub(crate) step_size: f64,
+    // Number of candidates sampled along the spiral per step.
+    pub(crate) candidates: usize,
+}
+
+impl AutoExplorer {
+    pub const DEFAULT_SCORING_WINDOW: usize = 8;
+    pub const DEFAULT_STEP_SIZE: f64 = 0.8;
+    pub const DEFAULT_CANDIDATES: usize = 64;
+
+    // Scores below this are considered featureless (solid interior or empty sea).
+    const MIN_SCORE: f64 = 1e-3;
+
+    pub(crate) fn new() -> Self {
+        AutoExplorer {
+            active: false,
+            scoring_window: AutoExplorer::DEFAULT_SCORING_WINDOW,
+            step_size: AutoExplorer::DEFAULT_STEP_SIZE,
+            candidates: AutoExplorer::DEFAULT_CANDIDATES,
+        }
+    }
+
+    pub(crate) fn toggle(&mut self) {
+        self.active = !self.active;
+    }
+
+    // Performs a single exploration step against the most recently drawn frame.
+    pub(crate) fn step(&self, renderer: &mut MandelbrotRenderer) {
+        // Wait for the previous step to settle so the scores aren't based on a preview or animation frame
+        if renderer.is_busy() {
+            return;
+        }
+
+        let (width, height) = renderer.dimensions();
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let center = (width as f64 / 2.0, height as f64 / 2.0);
+        let max_radius = center.0.min(center.1);
+
+        let mut best: Option<((f32, f32), f64)> = None;
+        for i in 0..self.candidates {
+            let t = i as f64 / self.candidates as f64;
+            // Archimedean spiral, three turns out to the edge of the view
+            let angle = t * 6.0 * std::f64::consts::PI;
+            let radius = t * max_radius;
+            let x = (center.0 + radius * angle.cos()).clamp(0.0, (width - 1) as f64);
+            let y = (center.1 + radius * angle.sin()).clamp(0.0, (height - 1) as f64);
+
+            // The generator's pixels are finer than the window's at render scales above 1
+            let scale = renderer.render_scale() as usize;
+            let score = renderer
+                .generator
+                .score_window(x as usize * scale, y as usize * scale, self.scoring_window * scale);
+
+            if best.is_none_or(|(_, best_score)| score > best_score) {
+                best = Some(((x as f32, y as f32), score));
+            }
+        }
+
+        match best {
+            Some((coords, score)) if score > AutoExplorer::MIN_SCORE => renderer.zoom(coords, self.step_size),
+            // Nothing interesting in view, back out so the boundary comes into frame again
+            _ => renderer.zoom((center.0 as f32, center.1 as f32), 1.0 / self.step_size),
+        }
+    }
+}
@@ -0,0 +1,140 @@
+// Writing rendered frames out as images.
+
+use image::{ColorType, ImageResult};
+use log::error;
+use visions_of_mandelbrot::{DoubleDouble, MandelbrotRenderer, ViewParams};
+
+// Width of high resolution exports, 4K UHD.
+pub(crate) const HIGH_RESOLUTION_WIDTH: usize = 3840;
+
+// Fraction of the view's width kept from one zoom movie frame to the next, a doubling in magnification about every 14
+// frames.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) const ZOOM_MOVIE_FACTOR: f64 = 0.95;
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn encode_png(width: usize, height: usize, buf: &[u8]) -> ImageResult<Vec<u8>> {
+    use image::codecs::png::PngEncoder;
+    use image::ImageEncoder;
+
+    let mut bytes = Vec::new();
+    PngEncoder::new(&mut bytes).write_image(buf, width as u32, height as u32, ColorType::Rgba8)?;
+    Ok(bytes)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn save_png(path: &std::path::Path, width: usize, height: usize, buf: &[u8]) -> ImageResult<()> {
+    image::save_buffer(path, buf, width as u32, height as u32, ColorType::Rgba8)
+}
+
+// Hands the PNG to the browser as a file download.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn download_png(filename: &str, bytes: &[u8]) -> Result<(), wasm_bindgen::JsValue> {
+    use wasm_bindgen::JsCast;
+
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes));
+    let mut options = web_sys::BlobPropertyBag::new();
+    options.type_("image/png");
+    let blob = web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+
+    let document = web_sys::window()
+        .and_then(|win| win.document())
+        .ok_or("couldn't access document")?;
+    let anchor = document
+        .create_element("a")?
+        .dyn_into::<web_sys::HtmlAnchorElement>()?;
+    anchor.set_href(&url);
+    anchor.set_download(filename);
+    anchor.click();
+
+    web_sys::Url::revoke_object_url(&url)
+}
+
+fn timestamp() -> u64 {
+    #[cfg(target_arch = "wasm32")]
+        {
+            (js_sys::Date::now() / 1000.0) as u64
+        }
+
+    #[cfg(not(target_arch = "wasm32"))]
+        {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default()
+        }
+}
+
+// Fully computes the current view at the renderer's size and writes it to a PNG file, handing back the RGBA frame.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn save_render(renderer: &MandelbrotRenderer, path: &std::path::Path) -> ImageResult<Vec<u8>> {
+    let (width, height) = renderer.dimensions();
+    let buf = renderer.render_to_image(width, height);
+    save_png(path, width, height, &buf)?;
+    Ok(buf)
+}
+
+// Writes `frames` fully computed views centered on `target` to `directory` as frame_0000.png onwards, starting at the
+// current magnification and zooming in by `factor` of the width per frame. Frames are rendered at the renderer's size
+// whatever the generator's resolution is.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn save_zoom_movie(
+    renderer: &mut MandelbrotRenderer,
+    target: (DoubleDouble, DoubleDouble),
+    frames: usize,
+    factor: f64,
+    directory: &std::path::Path,
+) -> ImageResult<()> {
+    std::fs::create_dir_all(directory)?;
+    let (width, height) = renderer.dimensions();
+    let magnification = renderer.generator.magnification();
+    for frame in 0..frames {
+        renderer.generator.set_center_and_zoom(target.0, target.1, magnification / factor.powi(frame as i32));
+        let path = directory.join(format!("frame_{:04}.png", frame));
+        save_png(&path, width, height, &renderer.render_to_image(width, height))?;
+        log::info!("saved {}", path.display());
+    }
+    Ok(())
+}
+
+// Renders the current view at `HIGH_RESOLUTION_WIDTH`, keeping the window's aspect ratio, and saves it like a
+// screenshot.
+pub(crate) fn save_high_resolution(renderer: &MandelbrotRenderer) {
+    let (width, height) = renderer.dimensions();
+    let scale = HIGH_RESOLUTION_WIDTH as f64 / width.max(1) as f64;
+    let high_height = ((height as f64 * scale).round() as usize).max(1);
+
+    let buffer = renderer.render_to_image(HIGH_RESOLUTION_WIDTH, high_height);
+    save_screenshot(HIGH_RESOLUTION_WIDTH, high_height, &buffer, renderer.generator.view());
+}
+
+// Saves an RGBA frame of `view` as a timestamped PNG and records it in the gallery, or downloads it on the web.
+// Failures are logged rather than fatal.
+#[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+pub(crate) fn save_screenshot(width: usize, height: usize, buf: &[u8], view: ViewParams<DoubleDouble>) {
+    let filename = format!("mandelbrot_{}.png", timestamp());
+
+    #[cfg(not(target_arch = "wasm32"))]
+        {
+            match save_png(std::path::Path::new(&filename), width, height, buf) {
+                Ok(()) => {
+                    log::info!("saved screenshot to {}", filename);
+                    crate::gallery::append(&filename, view);
+                }
+                Err(e) => error!("saving screenshot to {} failed: {}", filename, e),
+            }
+        }
+
+    #[cfg(target_arch = "wasm32")]
+        {
+            match encode_png(width, height, buf) {
+                Ok(bytes) => {
+                    if let Err(e) = download_png(&filename, &bytes) {
+                        error!("downloading screenshot failed: {:?}", e);
+                    }
+                }
+                Err(e) => error!("encoding screenshot failed: {}", e),
+            }
+        }
+}
@@ -0,0 +1,131 @@
+// A record of every screenshot saved on the desktop, one `[[shot]]` entry per PNG with its file name and view, so any
+// of them can be opened again with `--gallery`. Entries are only ever appended, each in a single write to a file
+// opened for appending, so several windows saving at once don't interleave their entries.
+
+use crate::bookmarks::Bookmarks;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use visions_of_mandelbrot::{DoubleDouble, ViewParams};
+
+// Looked up in the working directory, next to the screenshots.
+pub(crate) const GALLERY_FILE: &str = "gallery.toml";
+
+const ENTRY_HEADER: &str = "[[shot]]";
+
+#[derive(Serialize, Deserialize)]
+struct Shot {
+    file: String,
+    view: ViewParams<DoubleDouble>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Gallery {
+    shot: Vec<Shot>,
+}
+
+// Adds a saved screenshot to the gallery. Failures are logged, the screenshot itself is already saved.
+pub(crate) fn append(file: &str, view: ViewParams<DoubleDouble>) {
+    let entry = Gallery {
+        shot: vec![Shot { file: file.to_string(), view }],
+    };
+    let text = match toml::to_string(&entry) {
+        Ok(text) => format!("\n{}", text),
+        Err(e) => {
+            error!("failed to record {} in {}: {}", file, GALLERY_FILE, e);
+            return;
+        }
+    };
+
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(GALLERY_FILE)
+        .and_then(|mut gallery| gallery.write_all(text.as_bytes()));
+    if let Err(e) = written {
+        error!("failed to record {} in {}: {}", file, GALLERY_FILE, e);
+    }
+}
+
+// The gallery's screenshots as bookmarks named 1, 2 and so on from the newest, kept for the session only. Entries are
+// parsed one at a time, a malformed one, like a half-written entry from a crash, is logged and skipped.
+pub(crate) fn load(path: &Path) -> Bookmarks {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            error!("failed to read {}: {}", path.display(), e);
+            return Bookmarks::session(Default::default());
+        }
+    };
+
+    let mut entries = text.split(ENTRY_HEADER);
+    if entries.next().is_some_and(|preamble| !preamble.trim().is_empty()) {
+        warn!("ignoring text before the first entry of {}", path.display());
+    }
+
+    let shots: Vec<Shot> = entries
+        .enumerate()
+        .filter_map(|(index, entry)| match toml::from_str::<Gallery>(&format!("{}{}", ENTRY_HEADER, entry)) {
+            Ok(gallery) => gallery.shot.into_iter().next(),
+            Err(e) => {
+                warn!("skipping entry {} of {}: {}", index + 1, path.display(), e);
+                None
+            }
+        })
+        .collect();
+
+    for (number, shot) in shots.iter().rev().enumerate() {
+        info!("bookmark {}: {}", number + 1, shot.file);
+    }
+    Bookmarks::session(
+        shots
+            .into_iter()
+            .rev()
+            .enumerate()
+            .map(|(number, shot)| ((number + 1).to_string(), shot.view))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view(x_min: f64) -> ViewParams<DoubleDouble> {
+        ViewParams {
+            x_min: x_min.into(),
+            x_max: (x_min + 1.0).into(),
+            y_min: (-0.5).into(),
+            y_max: 0.5.into(),
+            max_iterations: 500.0,
+        }
+    }
+
+    // An entry as `append` writes it.
+    fn entry(file: &str, view: ViewParams<DoubleDouble>) -> String {
+        let gallery = Gallery {
+            shot: vec![Shot { file: file.to_string(), view }],
+        };
+        format!("\n{}", toml::to_string(&gallery).unwrap())
+    }
+
+    #[test]
+    fn loading_skips_malformed_entries_and_numbers_from_the_newest() {
+        let older = entry("older.png", view(-2.0));
+        let truncated = entry("truncated.png", view(-1.0));
+        let truncated = &truncated[..truncated.len() / 2];
+        let newer = entry("newer.png", view(0.0));
+        let text = format!("# written by hand\n{}{}{}", older, truncated, newer);
+
+        let path = std::env::temp_dir().join(format!("gallery-test-{}.toml", std::process::id()));
+        fs::write(&path, text).unwrap();
+        let bookmarks = load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(bookmarks.list().collect::<Vec<_>>(), ["1", "2"]);
+        assert_eq!(bookmarks.jump("1"), Some(view(0.0)));
+        assert_eq!(bookmarks.jump("2"), Some(view(-2.0)));
+    }
+}
@@ -0,0 +1,115 @@
+// Gamepad navigation on the native build: the left stick pans, the right trigger zooms in and the left one out, and the
+// south face button (A on an Xbox layout) picks a random palette. The stick and triggers are read once per frame and
+// scaled by the time since the last one, so the view moves at the same speed however fast frames come. Without a
+// gamepad, or without a backend for them on this platform, it all does nothing.
+
+use gilrs::{Axis, Button, EventType, Gilrs};
+use instant::Instant;
+use log::{info, warn};
+
+// Fraction of the view's extent the stick pans per second at full deflection.
+const PAN_SPEED: f32 = 0.8;
+
+// Factor the view's range scales by per second with a trigger fully pulled.
+const ZOOM_RATE: f64 = 0.25;
+
+// Longest step taken at once, so the first poll after a long idle doesn't jump the view.
+const MAX_STEP: f32 = 0.1;
+
+// Stick and trigger readings below this count as resting, worn sticks rarely settle at exactly zero.
+const DEAD_ZONE: f32 = 0.15;
+
+// Navigation since the last poll, pan in pixels like a mouse drag.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Navigation {
+    // The stick or a trigger just left rest, the starting view belongs on the undo history.
+    pub(crate) started: bool,
+    pub(crate) pan: (f32, f32),
+    // Factor the view's range scales by about its middle, below one zooms in.
+    pub(crate) zoom: Option<f64>,
+    pub(crate) randomize_palette: bool,
+}
+
+pub(crate) struct GamepadInput {
+    gilrs: Option<Gilrs>,
+    last_poll: Instant,
+    moving: bool,
+}
+
+impl GamepadInput {
+    pub(crate) fn new() -> Self {
+        let gilrs = match Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(e) => {
+                warn!("gamepads unavailable: {}", e);
+                None
+            }
+        };
+        GamepadInput {
+            gilrs,
+            last_poll: Instant::now(),
+            moving: false,
+        }
+    }
+
+    pub(crate) fn is_connected(&self) -> bool {
+        self.gilrs.as_ref().is_some_and(|gilrs| gilrs.gamepads().next().is_some())
+    }
+
+    // A stick or trigger is held, the view keeps moving without any other input arriving.
+    pub(crate) fn is_moving(&self) -> bool {
+        self.moving
+    }
+
+    // Drains pending gamepad events and reads the stick and triggers of every connected gamepad. `width` and `height`
+    // are the view's, in the pixels the pan is given in.
+    pub(crate) fn poll(&mut self, width: usize, height: usize) -> Navigation {
+        let mut navigation = Navigation::default();
+        let now = Instant::now();
+        let dt = now.duration_since(self.last_poll).as_secs_f32().min(MAX_STEP);
+        self.last_poll = now;
+
+        let gilrs = match &mut self.gilrs {
+            Some(gilrs) => gilrs,
+            None => return navigation,
+        };
+
+        while let Some(event) = gilrs.next_event() {
+            match event.event {
+                EventType::ButtonPressed(Button::South, _) => navigation.randomize_palette = true,
+                EventType::Connected => info!("gamepad connected: {}", gilrs.gamepad(event.id).name()),
+                EventType::Disconnected => info!("gamepad disconnected"),
+                _ => {}
+            }
+        }
+
+        let (mut stick_x, mut stick_y, mut zoom) = (0.0, 0.0, 0.0);
+        for (_, gamepad) in gilrs.gamepads() {
+            stick_x += dead_zone(gamepad.value(Axis::LeftStickX));
+            stick_y += dead_zone(gamepad.value(Axis::LeftStickY));
+            let trigger = |button| dead_zone(gamepad.button_data(button).map_or(0.0, |data| data.value()));
+            zoom += trigger(Button::RightTrigger2) - trigger(Button::LeftTrigger2);
+        }
+        let (stick_x, stick_y, zoom) = (stick_x.clamp(-1.0, 1.0), stick_y.clamp(-1.0, 1.0), zoom.clamp(-1.0, 1.0));
+
+        let moving = stick_x != 0.0 || stick_y != 0.0 || zoom != 0.0;
+        navigation.started = moving && !self.moving;
+        self.moving = moving;
+
+        // Pushing the stick right looks right, which drags the content left. Its y axis points up, the view's down.
+        let speed = PAN_SPEED * dt;
+        navigation.pan = (-stick_x * speed * width as f32, stick_y * speed * height as f32);
+        if zoom != 0.0 {
+            navigation.zoom = Some(ZOOM_RATE.powf(zoom as f64 * dt as f64));
+        }
+        navigation
+    }
+}
+
+fn dead_zone(value: f32) -> f32 {
+    if value.abs() < DEAD_ZONE {
+        0.0
+    } else {
+        value
+    }
+}
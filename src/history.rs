@@ -0,0 +1,102 @@
+use crate::double_double::DoubleDouble;
+use crate::mandelbrot::ViewParams;
+
+// Undo and redo stacks of navigated views. Recording a new view drops whatever could have been redone.
+pub(crate) struct ViewHistory {
+    undo: Vec<ViewParams<DoubleDouble>>,
+    redo: Vec<ViewParams<DoubleDouble>>,
+    // Most views kept for undo, the oldest are dropped first.
+    capacity: usize,
+}
+
+impl ViewHistory {
+    pub const DEFAULT_CAPACITY: usize = 100;
+
+    pub(crate) fn new() -> Self {
+        ViewHistory {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            capacity: ViewHistory::DEFAULT_CAPACITY,
+        }
+    }
+
+    // Remembers the view about to be navigated away from.
+    pub(crate) fn record(&mut self, view: ViewParams<DoubleDouble>) {
+        if self.undo.last() == Some(&view) {
+            return;
+        }
+
+        self.undo.push(view);
+        if self.undo.len() > self.capacity {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    // Returns the view to go back to, keeping the current one for redo.
+    pub(crate) fn undo(&mut self, current: ViewParams<DoubleDouble>) -> Option<ViewParams<DoubleDouble>> {
+        let view = self.undo.pop()?;
+        self.redo.push(current);
+        Some(view)
+    }
+
+    pub(crate) fn redo(&mut self, current: ViewParams<DoubleDouble>) -> Option<ViewParams<DoubleDouble>> {
+        let view = self.redo.pop()?;
+        self.undo.push(current);
+        Some(view)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view(x_min: f64) -> ViewParams<DoubleDouble> {
+        ViewParams {
+            x_min: x_min.into(),
+            x_max: (x_min + 1.0).into(),
+            y_min: (-0.5).into(),
+            y_max: 0.5.into(),
+            max_iterations: 500.0,
+        }
+    }
+
+    #[test]
+    fn the_oldest_views_are_dropped_past_the_capacity() {
+        let mut history = ViewHistory::new();
+        let recorded = ViewHistory::DEFAULT_CAPACITY + 5;
+        for i in 0..recorded {
+            history.record(view(i as f64));
+        }
+
+        let mut current = view(recorded as f64);
+        let mut undone = Vec::new();
+        while let Some(previous) = history.undo(current) {
+            undone.push(previous);
+            current = previous;
+        }
+        assert_eq!(undone.len(), ViewHistory::DEFAULT_CAPACITY);
+        assert_eq!(undone.first(), Some(&view((recorded - 1) as f64)));
+        assert_eq!(undone.last(), Some(&view(5.0)));
+    }
+
+    #[test]
+    fn recording_clears_redo() {
+        let mut history = ViewHistory::new();
+        history.record(view(0.0));
+        assert_eq!(history.undo(view(1.0)), Some(view(0.0)));
+
+        history.record(view(0.0));
+        assert_eq!(history.redo(view(2.0)), None);
+    }
+
+    #[test]
+    fn recording_the_last_view_again_is_ignored() {
+        let mut history = ViewHistory::new();
+        history.record(view(0.0));
+        history.record(view(0.0));
+
+        assert_eq!(history.undo(view(1.0)), Some(view(0.0)));
+        assert_eq!(history.undo(view(0.0)), None);
+    }
+}
@@ -0,0 +1,199 @@
+// The keys behind each keyboard action, remappable from the `[keys]` table of the config file. Keys are named as winit
+// names them, "P", "Key0", "PageUp", "LBracket" and so on. An action's Shift, Ctrl and Alt variants come along with its
+// key, so rebinding `randomize_palette` moves the palette seed prompt on Shift too. The number keys, Escape and the
+// prompt's Enter stay where they are.
+
+use serde::{Deserialize, Serialize};
+use winit::event::VirtualKeyCode;
+
+// The actions that only fire with Ctrl held, free to share keys with the plain ones.
+#[cfg(not(target_arch = "wasm32"))]
+const CONTROL_ACTIONS: [&str; 4] = ["undo", "redo", "copy", "paste"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct KeyBindings {
+    // Navigation
+    pub(crate) pan_left: VirtualKeyCode,
+    pub(crate) pan_right: VirtualKeyCode,
+    pub(crate) pan_up: VirtualKeyCode,
+    pub(crate) pan_down: VirtualKeyCode,
+    // About the middle of the view, by the same factor as a click
+    pub(crate) zoom_in: VirtualKeyCode,
+    pub(crate) zoom_out: VirtualKeyCode,
+    pub(crate) zoom_harder: VirtualKeyCode,
+    pub(crate) zoom_gentler: VirtualKeyCode,
+    pub(crate) zoom_snap: VirtualKeyCode,
+    pub(crate) animated_zoom: VirtualKeyCode,
+    pub(crate) auto_explore: VirtualKeyCode,
+    pub(crate) reset_view: VirtualKeyCode,
+    pub(crate) reset: VirtualKeyCode,
+
+    // History and clipboard, all with Ctrl held
+    pub(crate) undo: VirtualKeyCode,
+    pub(crate) redo: VirtualKeyCode,
+    pub(crate) copy: VirtualKeyCode,
+    pub(crate) paste: VirtualKeyCode,
+
+    // Computation
+    pub(crate) hybrid_precision: VirtualKeyCode,
+    pub(crate) progressive_preview: VirtualKeyCode,
+    pub(crate) pause: VirtualKeyCode,
+    pub(crate) resume: VirtualKeyCode,
+    pub(crate) background_computing: VirtualKeyCode,
+    pub(crate) supersampling: VirtualKeyCode,
+    pub(crate) bailout: VirtualKeyCode,
+    pub(crate) more_iterations: VirtualKeyCode,
+    pub(crate) fewer_iterations: VirtualKeyCode,
+    pub(crate) double_iterations: VirtualKeyCode,
+    pub(crate) halve_iterations: VirtualKeyCode,
+    pub(crate) auto_iterations: VirtualKeyCode,
+
+    // Fractal
+    pub(crate) julia: VirtualKeyCode,
+    pub(crate) julia_point: VirtualKeyCode,
+    pub(crate) formula: VirtualKeyCode,
+    pub(crate) power_up: VirtualKeyCode,
+    pub(crate) power_down: VirtualKeyCode,
+
+    // Palette and coloring
+    pub(crate) randomize_palette: VirtualKeyCode,
+    pub(crate) palette_cycling: VirtualKeyCode,
+    pub(crate) longer_palette_period: VirtualKeyCode,
+    pub(crate) shorter_palette_period: VirtualKeyCode,
+    pub(crate) invert_palette: VirtualKeyCode,
+    pub(crate) escape_angle: VirtualKeyCode,
+    pub(crate) binary_decomposition: VirtualKeyCode,
+    pub(crate) distance_estimate: VirtualKeyCode,
+    pub(crate) histogram: VirtualKeyCode,
+    pub(crate) dithering: VirtualKeyCode,
+
+    // Overlays
+    pub(crate) components: VirtualKeyCode,
+    pub(crate) hud: VirtualKeyCode,
+    pub(crate) buddhabrot: VirtualKeyCode,
+    pub(crate) orbit: VirtualKeyCode,
+
+    // Files
+    pub(crate) screenshot: VirtualKeyCode,
+    pub(crate) save_view: VirtualKeyCode,
+    pub(crate) load_view: VirtualKeyCode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            pan_left: VirtualKeyCode::Left,
+            pan_right: VirtualKeyCode::Right,
+            pan_up: VirtualKeyCode::Up,
+            pan_down: VirtualKeyCode::Down,
+            zoom_in: VirtualKeyCode::NumpadAdd,
+            zoom_out: VirtualKeyCode::NumpadSubtract,
+            zoom_harder: VirtualKeyCode::Apostrophe,
+            zoom_gentler: VirtualKeyCode::Semicolon,
+            zoom_snap: VirtualKeyCode::Z,
+            animated_zoom: VirtualKeyCode::A,
+            auto_explore: VirtualKeyCode::E,
+            reset_view: VirtualKeyCode::Home,
+            reset: VirtualKeyCode::R,
+
+            undo: VirtualKeyCode::Z,
+            redo: VirtualKeyCode::Y,
+            copy: VirtualKeyCode::C,
+            paste: VirtualKeyCode::V,
+
+            hybrid_precision: VirtualKeyCode::H,
+            progressive_preview: VirtualKeyCode::Y,
+            pause: VirtualKeyCode::Space,
+            resume: VirtualKeyCode::Return,
+            background_computing: VirtualKeyCode::W,
+            supersampling: VirtualKeyCode::X,
+            bailout: VirtualKeyCode::N,
+            more_iterations: VirtualKeyCode::RBracket,
+            fewer_iterations: VirtualKeyCode::LBracket,
+            double_iterations: VirtualKeyCode::PageUp,
+            halve_iterations: VirtualKeyCode::PageDown,
+            auto_iterations: VirtualKeyCode::I,
+
+            julia: VirtualKeyCode::J,
+            julia_point: VirtualKeyCode::K,
+            formula: VirtualKeyCode::F,
+            power_up: VirtualKeyCode::Period,
+            power_down: VirtualKeyCode::Comma,
+
+            randomize_palette: VirtualKeyCode::P,
+            palette_cycling: VirtualKeyCode::T,
+            longer_palette_period: VirtualKeyCode::Equals,
+            shorter_palette_period: VirtualKeyCode::Minus,
+            invert_palette: VirtualKeyCode::Key0,
+            escape_angle: VirtualKeyCode::D,
+            binary_decomposition: VirtualKeyCode::B,
+            distance_estimate: VirtualKeyCode::Q,
+            histogram: VirtualKeyCode::G,
+            dithering: VirtualKeyCode::Slash,
+
+            components: VirtualKeyCode::C,
+            hud: VirtualKeyCode::O,
+            buddhabrot: VirtualKeyCode::U,
+            orbit: VirtualKeyCode::M,
+
+            screenshot: VirtualKeyCode::S,
+            save_view: VirtualKeyCode::V,
+            load_view: VirtualKeyCode::L,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl KeyBindings {
+    // Pairs of actions bound to the same key with the same modifiers held, each as (key, first action, second action).
+    pub(crate) fn conflicts(&self) -> Vec<(String, String, String)> {
+        let actions = match toml::Value::try_from(self) {
+            Ok(toml::Value::Table(actions)) => actions,
+            _ => return Vec::new(),
+        };
+
+        let control = |action: &String| CONTROL_ACTIONS.contains(&action.as_str());
+        let mut conflicts = Vec::new();
+        for (i, (first, first_key)) in actions.iter().enumerate() {
+            for (second, second_key) in actions.iter().skip(i + 1) {
+                if control(first) == control(second) && first_key == second_key {
+                    let key = first_key.as_str().unwrap_or_default().to_string();
+                    conflicts.push((key, first.clone(), second.clone()));
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_have_no_conflicts() {
+        assert_eq!(KeyBindings::default().conflicts(), []);
+    }
+
+    #[test]
+    fn sharing_a_plain_key_conflicts() {
+        let keys = KeyBindings {
+            zoom_in: VirtualKeyCode::P,
+            ..KeyBindings::default()
+        };
+        assert_eq!(
+            keys.conflicts(),
+            [("P".to_string(), "randomize_palette".to_string(), "zoom_in".to_string())]
+        );
+    }
+
+    #[test]
+    fn plain_and_control_actions_may_share_a_key() {
+        let keys = KeyBindings {
+            undo: VirtualKeyCode::P,
+            ..KeyBindings::default()
+        };
+        assert_eq!(keys.conflicts(), []);
+    }
+}
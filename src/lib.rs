@@ -0,0 +1,39 @@
+//! Escape-time rendering of the Mandelbrot set and its relatives.
+//!
+//! [`MandelbrotGenerator`] computes a view of the complex plane pixel by pixel, [`MandelbrotRenderer`] colors its
+//! output into an RGBA frame and handles navigation. Neither depends on a window, so frames can be rendered for
+//! export or tests as well as for display:
+//!
+//! ```
+//! use visions_of_mandelbrot::{MandelbrotGenerator, MandelbrotRenderer, PaletteKind};
+//!
+//! let generator = MandelbrotGenerator::new(64, 48, 200.0);
+//! let mut renderer = MandelbrotRenderer::new(64, 48, generator);
+//! renderer.set_builtin_palette(PaletteKind::Fire);
+//! let rgba = renderer.render_to_image(64, 48);
+//! assert_eq!(rgba.len(), 64 * 48 * 4);
+//! ```
+//!
+//! Palettes are lists of `(position, color)` control points in linear RGB, see [`PaletteKind::control_points`],
+//! [`MandelbrotRenderer::expand_palette`] and [`MandelbrotRenderer::set_palette_points`].
+
+#![deny(clippy::all)]
+#![forbid(unsafe_code)]
+
+mod buddhabrot;
+mod double_double;
+mod history;
+mod mandelbrot;
+#[cfg(not(target_arch = "wasm32"))]
+mod reference;
+#[cfg(not(target_arch = "wasm32"))]
+mod worker;
+
+pub use crate::double_double::DoubleDouble;
+pub use crate::mandelbrot::{
+    Bounds, BuildError, ColoringMode, Component, Formula, FractalType, GradientSpace, IterationGrid,
+    MandelbrotGenerator, MandelbrotGeneratorBuilder, MandelbrotRenderer, PaletteKind, PaletteScale, PixelValue, Session,
+    ViewParams, ZoomSnap,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::reference::compare_png;
@@ -1,12 +1,45 @@
 #![deny(clippy::all)]
 #![forbid(unsafe_code)]
 
-mod mandelbrot;
+#[cfg(not(target_arch = "wasm32"))]
+mod config;
+#[cfg(not(target_arch = "wasm32"))]
+mod gallery;
+mod bookmarks;
+mod clipboard;
+mod error;
+mod explorer;
+mod export;
+#[cfg(all(feature = "gamepad", not(target_arch = "wasm32")))]
+mod gamepad;
+mod keybindings;
+mod momentum;
+mod overlay;
+#[cfg(target_arch = "wasm32")]
+mod permalink;
+mod prompt;
+#[cfg(target_arch = "wasm32")]
+mod touch;
+#[cfg(not(target_arch = "wasm32"))]
+mod views;
 
-use crate::mandelbrot::{MandelbrotGenerator, MandelbrotRenderer};
-use log::error;
+use crate::bookmarks::Bookmarks;
+use crate::clipboard::Clipboard;
+use crate::error::Error;
+use crate::explorer::AutoExplorer;
+#[cfg(all(feature = "gamepad", not(target_arch = "wasm32")))]
+use crate::gamepad::GamepadInput;
+use crate::momentum::Momentum;
+use crate::prompt::{Prompt, PromptKind};
+use instant::{Duration, Instant};
+use log::{error, info, warn};
 use pixels::{PixelsBuilder, SurfaceTexture};
 use std::rc::Rc;
+#[cfg(not(target_arch = "wasm32"))]
+use num_traits::{Float, Num};
+#[cfg(not(target_arch = "wasm32"))]
+use visions_of_mandelbrot::DoubleDouble;
+use visions_of_mandelbrot::{ColoringMode, MandelbrotGenerator, MandelbrotRenderer, PaletteKind};
 use winit::dpi::LogicalSize;
 use winit::event::{Event, VirtualKeyCode};
 use winit::event_loop::{ControlFlow, EventLoop};
@@ -16,32 +49,154 @@ use winit_input_helper::WinitInputHelper;
 const WIDTH: u32 = 640;
 const HEIGHT: u32 = 480;
 
+// Fraction of the view moved per arrow key press.
+const PAN_STEP: f64 = 0.1;
+
+// Amount the iteration cap changes per bracket key press.
+const ITERATION_STEP: f64 = 100.0;
+// Degrees the normal map light turns per key press.
+const LIGHT_ANGLE_STEP: f64 = 15.0;
+
+// Factor the palette period grows or shrinks by per key press.
+const PALETTE_PERIOD_FACTOR: f64 = 2.0;
+
+// Zoom factor applied per scroll wheel notch.
+const SCROLL_ZOOM_FACTOR: f64 = 0.9;
+
+// Weight of the newest frame in the smoothed frame rate.
+const FPS_SMOOTHING: f64 = 0.1;
+
+// Number keys in order, used for the built-in palettes and the bookmarks.
+const NUMBER_KEYS: [VirtualKeyCode; 9] = [
+    VirtualKeyCode::Key1,
+    VirtualKeyCode::Key2,
+    VirtualKeyCode::Key3,
+    VirtualKeyCode::Key4,
+    VirtualKeyCode::Key5,
+    VirtualKeyCode::Key6,
+    VirtualKeyCode::Key7,
+    VirtualKeyCode::Key8,
+    VirtualKeyCode::Key9,
+];
+
+// Distance in pixels the mouse has to travel with the button held before a click becomes a drag.
+const DRAG_THRESHOLD: f32 = 3.0;
+
+// Longest gap between the two clicks of a double-click, and how far apart in pixels they may land.
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(300);
+const DOUBLE_CLICK_DISTANCE: f32 = 4.0;
+
+// Range scale of a double-click, on top of the zoom its first click already made.
+const DOUBLE_CLICK_ZOOM_FACTOR: f64 = 0.1;
+
+// How often an idle gamepad is checked for input, long enough not to keep the CPU busy.
+#[cfg(all(feature = "gamepad", not(target_arch = "wasm32")))]
+const GAMEPAD_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
 fn main() {
     #[cfg(target_arch = "wasm32")]
         {
             std::panic::set_hook(Box::new(console_error_panic_hook::hook));
             console_log::init_with_level(log::Level::Trace).expect("error initializing logger");
 
-            wasm_bindgen_futures::spawn_local(run());
+            wasm_bindgen_futures::spawn_local(async {
+                if let Err(e) = run().await {
+                    error!("{}", e);
+                }
+            });
         }
 
     #[cfg(not(target_arch = "wasm32"))]
         {
             env_logger::init();
 
-            pollster::block_on(run());
+            if let Err(e) = pollster::block_on(run()) {
+                error!("{}", e);
+                std::process::exit(1);
+            }
         }
 }
 
-async fn run() {
+// Returns only if startup fails, or after a headless render. Once the window is up the event loop never hands control
+// back.
+async fn run() -> Result<(), Error> {
+    // Command-line arguments take precedence over the config file
+    #[cfg(not(target_arch = "wasm32"))]
+    let config = config::Config::load();
+    #[cfg(not(target_arch = "wasm32"))]
+    let args = <Args as clap::Parser>::parse().with_config(&config);
+    #[cfg(not(target_arch = "wasm32"))]
+    let (width, height, max_iterations) = (
+        args.width.unwrap_or(WIDTH).max(1),
+        args.height.unwrap_or(HEIGHT).max(1),
+        args.max_iterations,
+    );
+    #[cfg(target_arch = "wasm32")]
+    let (width, height, max_iterations) = (WIDTH, HEIGHT, MandelbrotGenerator::DEFAULT_MAX_ITERATIONS);
+    #[cfg(not(target_arch = "wasm32"))]
+    let keys = config.key_bindings();
+    #[cfg(target_arch = "wasm32")]
+    let keys = keybindings::KeyBindings::default();
+
+    #[allow(unused_mut)]
+    let mut mandelbrot_set = MandelbrotGenerator::new(width as usize, height as usize, max_iterations);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    args.apply_bounds(&mut mandelbrot_set);
+
+    let mut mandelbrot_renderer = MandelbrotRenderer::new(width as usize, height as usize, mandelbrot_set);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        match args.palette {
+            Some(config::PaletteChoice::Random) => mandelbrot_renderer.randomize_palette(),
+            Some(config::PaletteChoice::Builtin(kind)) => mandelbrot_renderer.set_builtin_palette(kind),
+            None => {}
+        }
+        if let Some(factor) = config.zoom_factor {
+            mandelbrot_renderer.set_zoom_factor(factor);
+        }
+        if let Some(speed) = config.palette_cycle_speed {
+            mandelbrot_renderer.palette_cycle_speed = speed;
+        }
+        if let Some(caps) = config.nebulabrot_caps {
+            mandelbrot_renderer.nebulabrot_caps = caps;
+        }
+    }
+
+    // Batch renders stop here, before any window or GPU surface exists
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(path) = args.headless_output() {
+        let buf = export::save_render(&mandelbrot_renderer, path).map_err(|e| Error::Export(path.to_path_buf(), e))?;
+        info!("saved render to {}", path.display());
+
+        if let Some(reference) = &args.compare {
+            let (width, height) = mandelbrot_renderer.dimensions();
+            let difference = visions_of_mandelbrot::compare_png(reference, width, height, &buf, args.tolerance)
+                .map_err(|e| Error::Reference(reference.clone(), e))?;
+            if let Some(difference) = difference {
+                return Err(Error::Mismatch(reference.clone(), difference));
+            }
+            info!("render matches {}", reference.display());
+        }
+        return Ok(());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some((directory, target)) = args.zoom_movie() {
+        export::save_zoom_movie(&mut mandelbrot_renderer, target, args.frames, args.frame_zoom, directory)
+            .map_err(|e| Error::Export(directory.to_path_buf(), e))?;
+        info!("saved {} frames to {}", args.frames, directory.display());
+        return Ok(());
+    }
+
     let event_loop = EventLoop::new();
     let window = {
-        let size = LogicalSize::new(WIDTH as f64, HEIGHT as f64);
+        let size = LogicalSize::new(width as f64, height as f64);
         WindowBuilder::new()
-            .with_title("Visions of Mandelbrot")
+            .with_title(MandelbrotRenderer::TITLE)
             .with_inner_size(size)
-            .build(&event_loop)
-            .expect("WindowBuilder error")
+            .build(&event_loop)?
     };
 
     let window = Rc::new(window);
@@ -53,61 +208,122 @@ async fn run() {
 
             // Retrieve current width and height dimensions of browser client window
             let get_window_size = || {
-                let client_window = web_sys::window().unwrap();
-                LogicalSize::new(
-                    client_window.inner_width().unwrap().as_f64().unwrap(),
-                    client_window.inner_height().unwrap().as_f64().unwrap(),
-                )
+                let client_window = web_sys::window()?;
+                Some(LogicalSize::new(
+                    client_window.inner_width().ok()?.as_f64()?,
+                    client_window.inner_height().ok()?.as_f64()?,
+                ))
             };
 
             let window = Rc::clone(&window);
 
             // Initialize winit window with current dimensions of browser client
-            window.set_inner_size(get_window_size());
+            window.set_inner_size(get_window_size().ok_or_else(|| Error::Web("couldn't read the page size".into()))?);
+
+            // A view linked in the URL is restored at the page's size, so it spans the same width it was shared at
+            let size = window.inner_size();
+            mandelbrot_renderer.resize(size.width as usize, size.height as usize);
+            if let Some((re, im, zoom)) = permalink::read() {
+                mandelbrot_renderer.generator.set_center_and_zoom(re, im, zoom);
+            }
 
-            let client_window = web_sys::window().unwrap();
+            let client_window = web_sys::window().ok_or_else(|| Error::Web("couldn't access window".into()))?;
 
             // Attach winit canvas to body element
-            web_sys::window()
-                .and_then(|win| win.document())
+            let body = client_window
+                .document()
                 .and_then(|doc| doc.body())
-                .and_then(|body| {
-                    body.append_child(&web_sys::Element::from(window.canvas()))
-                        .ok()
-                })
-                .expect("couldn't append canvas to document body");
+                .ok_or_else(|| Error::Web("couldn't access document body".into()))?;
+            body.append_child(&web_sys::Element::from(window.canvas()))?;
 
             // Listen for resize event on browser client. Adjust winit window dimensions
-            // on event trigger
+            // on event trigger, keeping the old size if the page can't report one
             let closure = wasm_bindgen::closure::Closure::wrap(Box::new(move |_e: web_sys::Event| {
-                let size = get_window_size();
-                window.set_inner_size(size)
+                if let Some(size) = get_window_size() {
+                    window.set_inner_size(size)
+                }
             }) as Box<dyn FnMut(_)>);
-            client_window
-                .add_event_listener_with_callback("resize", closure.as_ref().unchecked_ref())
-                .unwrap();
+            client_window.add_event_listener_with_callback("resize", closure.as_ref().unchecked_ref())?;
             closure.forget();
         }
 
+    #[cfg(target_arch = "wasm32")]
+    let touch = {
+        use winit::platform::web::WindowExtWebSys;
+        touch::TouchInput::attach(&window.canvas(), event_loop.create_proxy())?
+    };
+
     let mut input = WinitInputHelper::new();
     let mut pixels = {
         let window_size = window.inner_size();
         let surface_texture =
             SurfaceTexture::new(window_size.width, window_size.height, window.as_ref());
-        PixelsBuilder::new(WIDTH, HEIGHT, surface_texture)
+        PixelsBuilder::new(width, height, surface_texture)
             .enable_vsync(true)
             .build_async()
-            .await
-            .expect("Pixels error")
+            .await?
     };
 
-    let mandelbrot_set = MandelbrotGenerator::new(WIDTH as usize, HEIGHT as usize, MandelbrotGenerator::DEFAULT_MAX_ITERATIONS);
-    let mut mandelbrot_renderer = MandelbrotRenderer::new(WIDTH as usize, HEIGHT as usize, mandelbrot_set);
+    let mut explorer = AutoExplorer::new();
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut bookmarks = args.gallery.as_deref().map_or_else(Bookmarks::load, gallery::load);
+    #[cfg(target_arch = "wasm32")]
+    let mut bookmarks = Bookmarks::load();
+    let mut drag_origin: Option<(f32, f32)> = None;
+    let mut dragging = false;
+    let mut momentum = Momentum::new();
+    #[cfg(all(feature = "gamepad", not(target_arch = "wasm32")))]
+    let mut gamepad = GamepadInput::new();
+    // Time and place of the last click that zoomed, a second one close by soon after makes a double-click.
+    let mut last_click: Option<(Instant, (f32, f32))> = None;
+    let mut selection_origin: Option<(f32, f32)> = None;
+    let mut show_hud = false;
+    // Orbit of the last traced point, drawn over the fractal while set.
+    let mut orbit: Option<Vec<(f64, f64)>> = None;
+    let mut last_frame = Instant::now();
+    let mut fps = 0.0;
+    let mut title = String::new();
+    // The view last written to the URL hash, see `permalink`.
+    #[cfg(target_arch = "wasm32")]
+    let mut url_hash = String::new();
+    // Text being typed in place of the usual key bindings.
+    let mut prompt: Option<Prompt> = None;
+    let clipboard = Clipboard::default();
 
     event_loop.run(move |event, _, control_flow| {
         // Draw the current frame
         if let Event::RedrawRequested(_) = event {
-            mandelbrot_renderer.draw(pixels.get_frame());
+            // Smoothed over the last few frames so the readout doesn't flicker
+            let now = Instant::now();
+            let frame_time = now.duration_since(last_frame).as_secs_f64();
+            last_frame = now;
+            if frame_time > 0.0 {
+                fps += (1.0 / frame_time - fps) * FPS_SMOOTHING;
+            }
+
+            let frame = pixels.get_frame();
+            mandelbrot_renderer.draw(frame);
+
+            if let (Some(origin), Some(mouse)) = (selection_origin, input.mouse()) {
+                let (width, height) = mandelbrot_renderer.dimensions();
+                overlay::draw_rectangle(frame, width, height, origin, mouse, [0xff, 0xff, 0xff, 0xff]);
+            }
+
+            if let Some(orbit) = &orbit {
+                let (width, height) = mandelbrot_renderer.dimensions();
+                overlay::draw_orbit(frame, width, height, &mandelbrot_renderer, orbit);
+            }
+
+            if show_hud {
+                let (width, height) = mandelbrot_renderer.dimensions();
+                overlay::draw_hud(frame, width, height, &mandelbrot_renderer, fps);
+            }
+
+            if let Some(prompt) = &prompt {
+                let (width, height) = mandelbrot_renderer.dimensions();
+                overlay::draw_prompt(frame, width, height, &prompt.display());
+            }
+
             if pixels
                 .render()
                 .map_err(|e| error!("pixels.render() failed: {:?}", e))
@@ -120,42 +336,750 @@ async fn run() {
 
         // Handle input events
         if input.update(&event) {
-            // Close events
-            if input.key_pressed(VirtualKeyCode::Escape) || input.quit() {
+            // Close events, Escape only cancels the prompt while one is open
+            if (input.key_pressed(VirtualKeyCode::Escape) && prompt.is_none()) || input.quit() {
                 *control_flow = ControlFlow::Exit;
                 return;
             }
 
-            // Zoom events
+            // Resize the window. Minimizing reports a zero size, which has nothing to draw into
+            if let Some(size) = input.window_resized().filter(|size| size.width > 0 && size.height > 0) {
+                pixels.resize_surface(size.width, size.height);
+                pixels.resize_buffer(size.width, size.height);
+
+                mandelbrot_renderer.resize(size.width as usize, size.height as usize);
+            }
+
+            // A prompt takes the whole keyboard until Enter submits it or Escape cancels it
+            if let Some(entry) = &mut prompt {
+                entry.type_text(&input.text());
+                if input.key_pressed(VirtualKeyCode::Return) {
+                    let submitted = match entry.kind {
+                        PromptKind::PaletteSeed => prompt::parse_seed(&entry.text).map(|seed| {
+                            mandelbrot_renderer.set_random_palette(seed);
+                            info!("palette seed: {}", seed);
+                        }),
+                        PromptKind::Coordinates => prompt::parse_coordinates(&entry.text).map(|(re, im, zoom)| {
+                            mandelbrot_renderer.set_center_and_zoom(re, im, zoom);
+                        }),
+                    };
+                    match submitted {
+                        Ok(()) => prompt = None,
+                        Err(e) => {
+                            warn!("{}: {}", entry.text, e);
+                            entry.error = Some(e);
+                        }
+                    }
+                } else if input.key_pressed(VirtualKeyCode::Escape) {
+                    prompt = None;
+                }
+
+                // Enter and Escape are done with here, neither should reach the handlers below
+                window.request_redraw();
+                return;
+            }
+
+            // Clipboard events, pasted text that isn't a location is ignored
+            let control = input.held_control();
+            if control && input.key_pressed(keys.copy) {
+                clipboard.copy(&mandelbrot_renderer.location());
+            }
+            if control && input.key_pressed(keys.paste) {
+                clipboard.request_paste();
+            }
+            if let Some(text) = clipboard.take_pasted() {
+                match prompt::parse_coordinates(&text) {
+                    Ok((re, im, zoom)) => mandelbrot_renderer.set_center_and_zoom(re, im, zoom),
+                    Err(e) => warn!("ignoring pasted text, not a location: {}", e.to_lowercase()),
+                }
+            }
+
+            // Pan events
             if input.mouse_pressed(0) {
-                // Left mouse
-                mandelbrot_renderer.zoom(input.mouse().unwrap(), 0.5);
+                if input.held_alt() && orbit.is_some() {
+                    // Traces a new point instead, without starting a drag or zoom
+                    orbit = input.mouse().map(|mouse| mandelbrot_renderer.orbit(mouse));
+                } else if input.held_shift() {
+                    selection_origin = input.mouse();
+                } else {
+                    drag_origin = input.mouse();
+                    dragging = false;
+                    momentum.stop();
+                }
+            }
+            if let (Some(origin), Some(mouse)) = (drag_origin, input.mouse()) {
+                if input.mouse_held(0) && !dragging {
+                    let (dx, dy) = (mouse.0 - origin.0, mouse.1 - origin.1);
+                    if dx.hypot(dy) > DRAG_THRESHOLD {
+                        dragging = true;
+                        momentum.start_drag();
+                        mandelbrot_renderer.record_view();
+                        mandelbrot_renderer.pan(dx, dy);
+                    }
+                } else if input.mouse_held(0) {
+                    let (dx, dy) = input.mouse_diff();
+                    if dx != 0.0 || dy != 0.0 {
+                        momentum.track(dx, dy);
+                        mandelbrot_renderer.pan(dx, dy);
+                    }
+                }
+            }
+            if let Some((dx, dy)) = momentum.step() {
+                mandelbrot_renderer.pan(dx, dy);
+            }
+
+            // Touch events, gathered between frames and applied at once
+            #[cfg(target_arch = "wasm32")]
+            {
+                let gesture = touch.take();
+                if gesture.started {
+                    mandelbrot_renderer.record_view();
+                }
+                let (dx, dy) = gesture.pan;
+                if dx != 0.0 || dy != 0.0 {
+                    mandelbrot_renderer.pan(dx, dy);
+                }
+                if let Some((center, factor)) = gesture.zoom {
+                    mandelbrot_renderer.zoom_immediately(center, factor);
+                }
+            }
+
+            // Gamepad events, read once per frame like touches
+            #[cfg(all(feature = "gamepad", not(target_arch = "wasm32")))]
+            {
+                let (width, height) = mandelbrot_renderer.dimensions();
+                let navigation = gamepad.poll(width, height);
+                if navigation.started {
+                    mandelbrot_renderer.record_view();
+                }
+                let (dx, dy) = navigation.pan;
+                if dx != 0.0 || dy != 0.0 {
+                    mandelbrot_renderer.pan(dx, dy);
+                }
+                if let Some(factor) = navigation.zoom {
+                    mandelbrot_renderer.zoom_immediately((width as f32 / 2.0, height as f32 / 2.0), factor);
+                }
+                if navigation.randomize_palette {
+                    mandelbrot_renderer.randomize_palette();
+                    info!("palette seed: {}", mandelbrot_renderer.palette_seed().unwrap_or_default());
+                }
+            }
+
+            let arrows = [keys.pan_left, keys.pan_right, keys.pan_up, keys.pan_down];
+            if arrows.iter().any(|&key| input.key_pressed(key)) {
+                // Combine every held arrow so diagonals move in both axes at once
+                let axis = |negative, positive| {
+                    (input.key_held(positive) as i32 - input.key_held(negative) as i32) as f64
+                };
+                let fx = axis(keys.pan_left, keys.pan_right);
+                let fy = axis(keys.pan_up, keys.pan_down);
+                mandelbrot_renderer.pan_by_fraction(fx * PAN_STEP, fy * PAN_STEP);
+            }
+
+            // Zoom events
+            if input.mouse_released(0) {
+                // Left mouse, only zooms if the press wasn't a drag
+                if let Some(origin) = selection_origin.take() {
+                    if let Some(mouse) = input.mouse() {
+                        mandelbrot_renderer.zoom_to_rect(origin, mouse);
+                    }
+                } else if dragging {
+                    momentum.release();
+                } else if let Some(origin) = drag_origin {
+                    let double_click = last_click.take().filter(|(time, position)| {
+                        time.elapsed() <= DOUBLE_CLICK_TIME
+                            && (origin.0 - position.0).hypot(origin.1 - position.1) <= DOUBLE_CLICK_DISTANCE
+                    });
+                    if double_click.is_some() {
+                        // The first click already brought the point to the middle of the view
+                        let (width, height) = mandelbrot_renderer.dimensions();
+                        mandelbrot_renderer.zoom((width as f32 / 2.0, height as f32 / 2.0), DOUBLE_CLICK_ZOOM_FACTOR);
+                    } else {
+                        mandelbrot_renderer.zoom_in(origin);
+                        last_click = Some((Instant::now(), origin));
+                    }
+                }
+                drag_origin = None;
+                dragging = false;
             } else if input.mouse_pressed(1) {
                 // Right mouse
-                mandelbrot_renderer.zoom(input.mouse().unwrap(), 2.0);
+                if let Some(mouse) = input.mouse() {
+                    mandelbrot_renderer.zoom_out(mouse);
+                }
+            }
+            if input.mouse_pressed(2) {
+                // Middle mouse
+                if let Some(mouse) = input.mouse() {
+                    mandelbrot_renderer.center_on(mouse);
+                }
+            }
+
+            let scroll = input.scroll_diff();
+            if scroll != 0.0 {
+                // Scrolling up zooms in, centered on the cursor or the middle of the view when it's off-window
+                let (width, height) = mandelbrot_renderer.dimensions();
+                let coords = input.mouse().unwrap_or((width as f32 / 2.0, height as f32 / 2.0));
+                mandelbrot_renderer.zoom(coords, SCROLL_ZOOM_FACTOR.powf(scroll as f64));
+            }
+
+            if input.key_pressed(keys.zoom_in) || input.key_pressed(keys.zoom_out) {
+                let (width, height) = mandelbrot_renderer.dimensions();
+                let middle = (width as f32 / 2.0, height as f32 / 2.0);
+                if input.key_pressed(keys.zoom_in) {
+                    mandelbrot_renderer.zoom_in(middle);
+                } else {
+                    mandelbrot_renderer.zoom_out(middle);
+                }
+            }
+
+            if input.key_pressed(keys.zoom_harder) || input.key_pressed(keys.zoom_gentler) {
+                mandelbrot_renderer.step_zoom_factor(input.key_pressed(keys.zoom_harder));
+                info!("zoom factor: {}", mandelbrot_renderer.zoom_factor());
+            }
+
+            // History events, Ctrl+Shift+Z redoes as well as Ctrl+Y
+            let undo = control && input.key_pressed(keys.undo) && !input.held_shift();
+            let redo = control && (input.key_pressed(keys.redo) || input.held_shift() && input.key_pressed(keys.undo));
+            if undo && !mandelbrot_renderer.undo() {
+                info!("nothing to undo");
+            }
+            if redo && !mandelbrot_renderer.redo() {
+                info!("nothing to redo");
+            }
+
+            // Zoom snapping events
+            if input.key_pressed(keys.zoom_snap) && !input.held_control() {
+                let snap = mandelbrot_renderer.generator.zoom_snap.next();
+                mandelbrot_renderer.generator.zoom_snap = snap;
+                info!("zoom snapping: {:?}", snap);
+            }
+
+            // Precision events
+            if input.key_pressed(keys.hybrid_precision) {
+                let generator = &mut mandelbrot_renderer.generator;
+                generator.hybrid_precision = !generator.hybrid_precision;
+                info!("hybrid precision: {}", generator.hybrid_precision);
+                mandelbrot_renderer.recalculate();
+            }
+            if input.key_pressed(keys.progressive_preview) && !input.held_control() {
+                mandelbrot_renderer.toggle_progressive();
+                info!("progressive preview: {}", mandelbrot_renderer.progressive);
+            }
+            if input.key_pressed(keys.pause) && !mandelbrot_renderer.paused {
+                mandelbrot_renderer.pause();
+                info!("computation paused");
+            }
+            if input.key_pressed(keys.resume) && mandelbrot_renderer.paused {
+                mandelbrot_renderer.resume();
+                info!("computation resumed");
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            if input.key_pressed(keys.background_computing) {
+                let enabled = !mandelbrot_renderer.is_computing_in_background();
+                mandelbrot_renderer.set_background_computing(enabled);
+                info!("background computing: {}", enabled);
+            }
+            if input.key_pressed(keys.supersampling) {
+                if input.held_alt() {
+                    mandelbrot_renderer.toggle_linear_averaging();
+                    info!("render scale averaging in linear light: {}", mandelbrot_renderer.is_averaging_linear());
+                } else if input.held_shift() {
+                    mandelbrot_renderer.cycle_render_scale();
+                    info!("render scale: {}", mandelbrot_renderer.render_scale());
+                } else {
+                    mandelbrot_renderer.cycle_aa_samples();
+                    info!("samples per pixel: {}", mandelbrot_renderer.generator.aa_samples());
+                }
+            }
+            if input.key_pressed(keys.bailout) {
+                mandelbrot_renderer.cycle_bailout();
+                info!("bailout: {}", mandelbrot_renderer.generator.bailout());
+            }
+
+            // Iteration events
+            if input.key_pressed(keys.more_iterations) || input.key_pressed(keys.fewer_iterations) {
+                let up = input.key_pressed(keys.more_iterations);
+                if input.held_shift() {
+                    match mandelbrot_renderer.coloring_mode {
+                        ColoringMode::StripeAverage { frequency } => {
+                            let step = if up { 1.0 } else { -1.0 };
+                            let frequency = (frequency + step).clamp(1.0, MandelbrotRenderer::MAX_STRIPE_FREQUENCY);
+                            mandelbrot_renderer.set_coloring_mode(ColoringMode::StripeAverage { frequency });
+                            info!("stripe frequency: {}", frequency);
+                        }
+                        ColoringMode::NormalMap { light_angle } => {
+                            let step = if up { LIGHT_ANGLE_STEP } else { -LIGHT_ANGLE_STEP };
+                            let light_angle = (light_angle + step).rem_euclid(360.0);
+                            mandelbrot_renderer.set_coloring_mode(ColoringMode::NormalMap { light_angle });
+                            info!("light angle: {}", light_angle);
+                        }
+                        _ => {}
+                    }
+                } else {
+                    let step = if up { ITERATION_STEP } else { -ITERATION_STEP };
+                    let max_iterations = mandelbrot_renderer.generator.max_iterations() + step;
+                    mandelbrot_renderer.set_max_iterations(max_iterations);
+                    info!("max iterations: {}", mandelbrot_renderer.generator.max_iterations());
+                }
+            }
+            if input.key_pressed(keys.double_iterations) || input.key_pressed(keys.halve_iterations) {
+                mandelbrot_renderer.step_max_iterations_power_of_two(input.key_pressed(keys.double_iterations));
+                info!("max iterations: {}", mandelbrot_renderer.generator.max_iterations());
+            }
+            if input.key_pressed(keys.auto_iterations) {
+                let generator = &mut mandelbrot_renderer.generator;
+                generator.set_auto_iterations(!generator.auto_iterations());
+                info!("auto iterations: {}", generator.auto_iterations());
+            }
+
+            // Fractal events
+            if input.key_pressed(keys.julia) {
+                mandelbrot_renderer.toggle_julia();
+            }
+            if input.key_pressed(keys.formula) {
+                mandelbrot_renderer.cycle_formula();
+                info!("formula: {:?}", mandelbrot_renderer.generator.formula());
+            }
+            if input.key_pressed(keys.power_up) || input.key_pressed(keys.power_down) {
+                mandelbrot_renderer.step_power(if input.key_pressed(keys.power_up) { 1 } else { -1 });
+                info!("power: {}", mandelbrot_renderer.generator.power());
+            }
+            if input.key_pressed(keys.julia_point) {
+                if let Some(mouse) = input.mouse() {
+                    mandelbrot_renderer.set_julia_c_at(mouse);
+                }
             }
 
             // Palette events
-            if input.key_pressed(VirtualKeyCode::P) {
-                mandelbrot_renderer.randomize_palette();
+            if input.key_pressed(keys.randomize_palette) {
+                if input.held_shift() {
+                    prompt = Some(Prompt::new(PromptKind::PaletteSeed));
+                } else if input.held_alt() {
+                    mandelbrot_renderer.fade_to_random_palette();
+                    info!("palette seed: {}", mandelbrot_renderer.palette_seed().unwrap_or_default());
+                } else {
+                    mandelbrot_renderer.randomize_palette();
+                    info!("palette seed: {}", mandelbrot_renderer.palette_seed().unwrap_or_default());
+                }
+            }
+            if input.key_pressed(keys.palette_cycling) {
+                if input.held_shift() {
+                    mandelbrot_renderer.set_gradient_space(mandelbrot_renderer.gradient_space().next());
+                    info!("gradient space: {:?}", mandelbrot_renderer.gradient_space());
+                } else {
+                    mandelbrot_renderer.toggle_palette_cycling();
+                }
+            }
+            if input.key_pressed(keys.longer_palette_period) || input.key_pressed(keys.shorter_palette_period) {
+                let forward = input.key_pressed(keys.longer_palette_period);
+                if input.held_shift() {
+                    let step = MandelbrotRenderer::PALETTE_OFFSET_STEP;
+                    mandelbrot_renderer.shift_palette_offset(if forward { step } else { -step });
+                    info!("palette offset: {}", mandelbrot_renderer.palette_offset());
+                } else {
+                    let factor = if forward { PALETTE_PERIOD_FACTOR } else { 1.0 / PALETTE_PERIOD_FACTOR };
+                    mandelbrot_renderer.scale_palette_period(factor);
+                    info!("palette period: {}", mandelbrot_renderer.palette_period());
+                }
+            }
+            if input.key_pressed(keys.invert_palette) {
+                if input.held_shift() {
+                    mandelbrot_renderer.set_palette_scale(mandelbrot_renderer.palette_scale().next());
+                    info!("palette scale: {:?}", mandelbrot_renderer.palette_scale());
+                } else {
+                    mandelbrot_renderer.invert_palette();
+                    info!("palette inverted: {}", mandelbrot_renderer.is_palette_inverted());
+                }
+            }
+            let modified = input.held_control() || input.held_alt();
+            for (key, kind) in NUMBER_KEYS.into_iter().zip(PaletteKind::ALL) {
+                if input.key_pressed(key) && !modified {
+                    mandelbrot_renderer.set_builtin_palette(kind);
+                }
+            }
+
+            // Bookmark events, named after their number key. Jumping takes Alt rather than a plain number key, those
+            // already switch between the built-in palettes
+            for (number, key) in NUMBER_KEYS.into_iter().enumerate() {
+                if !input.key_pressed(key) {
+                    continue;
+                }
+
+                let name = (number + 1).to_string();
+                if input.held_control() && input.held_shift() {
+                    if bookmarks.remove(&name).is_some() {
+                        info!("removed bookmark {}", name);
+                    }
+                } else if input.held_control() {
+                    bookmarks.add(&name, mandelbrot_renderer.generator.view());
+                    info!("stored bookmark {}", name);
+                } else if input.held_alt() {
+                    match bookmarks.jump(&name) {
+                        Some(view) => {
+                            mandelbrot_renderer.record_view();
+                            mandelbrot_renderer.set_view(view);
+                        }
+                        None => info!("no bookmark {}, stored: {:?}", name, bookmarks.list().collect::<Vec<_>>()),
+                    }
+                }
+            }
+
+            // Animation events
+            if input.key_pressed(keys.animated_zoom) {
+                mandelbrot_renderer.animate_zoom = !mandelbrot_renderer.animate_zoom;
+                info!("animated zoom: {}", mandelbrot_renderer.animate_zoom);
+            }
+
+            // Auto-explore events
+            if input.key_pressed(keys.auto_explore) {
+                explorer.toggle();
+            }
+            if explorer.active {
+                explorer.step(&mut mandelbrot_renderer);
+            }
+
+            // Coloring events
+            if input.key_pressed(keys.escape_angle) {
+                let mode = match (mandelbrot_renderer.coloring_mode, input.held_shift()) {
+                    (ColoringMode::StripeAverage { .. }, true) | (ColoringMode::EscapeAngle { .. }, false) => {
+                        ColoringMode::Linear
+                    }
+                    (_, true) => {
+                        ColoringMode::StripeAverage { frequency: MandelbrotRenderer::DEFAULT_STRIPE_FREQUENCY }
+                    }
+                    (_, false) => ColoringMode::EscapeAngle { binary: false },
+                };
+                mandelbrot_renderer.set_coloring_mode(mode);
+            }
+            if input.key_pressed(keys.distance_estimate) {
+                let mode = match (mandelbrot_renderer.coloring_mode, input.held_shift()) {
+                    (ColoringMode::NormalMap { .. }, true) | (ColoringMode::DistanceEstimate, false) => {
+                        ColoringMode::Linear
+                    }
+                    (_, true) => ColoringMode::NormalMap { light_angle: MandelbrotRenderer::DEFAULT_LIGHT_ANGLE },
+                    (_, false) => ColoringMode::DistanceEstimate,
+                };
+                mandelbrot_renderer.set_coloring_mode(mode);
+            }
+            if input.key_pressed(keys.histogram) {
+                if input.held_shift() {
+                    prompt = Some(Prompt::new(PromptKind::Coordinates));
+                } else {
+                    let mode = match mandelbrot_renderer.coloring_mode {
+                        ColoringMode::Histogram => ColoringMode::Linear,
+                        _ => ColoringMode::Histogram,
+                    };
+                    mandelbrot_renderer.set_coloring_mode(mode);
+                }
+            }
+            if input.key_pressed(keys.binary_decomposition) {
+                if let ColoringMode::EscapeAngle { binary } = mandelbrot_renderer.coloring_mode {
+                    mandelbrot_renderer.set_coloring_mode(ColoringMode::EscapeAngle { binary: !binary });
+                }
+            }
+            if input.key_pressed(keys.dithering) {
+                mandelbrot_renderer.toggle_dithering();
+                info!("dithering: {}", mandelbrot_renderer.is_dithering());
+            }
+
+            // Debug overlay events
+            if input.key_pressed(keys.components) && !control {
+                if input.held_shift() {
+                    let generator = &mut mandelbrot_renderer.generator;
+                    generator.use_cardioid_check = !generator.use_cardioid_check;
+                    info!("cardioid check: {}", generator.use_cardioid_check);
+                    mandelbrot_renderer.recalculate();
+                } else {
+                    mandelbrot_renderer.toggle_components();
+                }
+            }
+            if input.key_pressed(keys.hud) {
+                show_hud = !show_hud;
+            }
+            if input.key_pressed(keys.buddhabrot) {
+                if input.held_shift() {
+                    mandelbrot_renderer.toggle_nebulabrot();
+                    info!("nebulabrot {:?}: {}", mandelbrot_renderer.nebulabrot_caps, mandelbrot_renderer.is_nebulabrot());
+                } else {
+                    mandelbrot_renderer.toggle_buddhabrot();
+                    info!("buddhabrot: {}", mandelbrot_renderer.is_buddhabrot());
+                }
+            }
+            if input.key_pressed(keys.orbit) {
+                orbit = match orbit {
+                    Some(_) => None,
+                    None => input.mouse().map(|mouse| mandelbrot_renderer.orbit(mouse)),
+                };
+            }
+
+            // View, palette and session file events, Shift picks the palette and Alt the whole session. Alt also saves the
+            // iteration counts
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                if input.key_pressed(keys.save_view) && !control {
+                    if input.held_alt() {
+                        views::save_session(&mandelbrot_renderer);
+                    } else if input.held_shift() {
+                        views::save_palette(&mandelbrot_renderer);
+                    } else {
+                        views::save_view(&mandelbrot_renderer.generator.view());
+                    }
+                }
+
+                if input.key_pressed(keys.load_view) {
+                    if input.held_alt() {
+                        views::load_session(&mut mandelbrot_renderer);
+                    } else if input.held_shift() {
+                        views::load_palette(&mut mandelbrot_renderer);
+                    } else if let Some(view) = views::load_view() {
+                        mandelbrot_renderer.set_view(view);
+                    }
+                }
+
+                if input.key_pressed(keys.screenshot) && input.held_alt() {
+                    views::save_iterations(&mandelbrot_renderer.generator);
+                }
+            }
+
+            // Screenshot events
+            if input.key_pressed(keys.screenshot) && !input.held_alt() {
+                if input.held_shift() {
+                    export::save_high_resolution(&mandelbrot_renderer);
+                } else {
+                    let (width, height) = mandelbrot_renderer.dimensions();
+                    let view = mandelbrot_renderer.generator.view();
+                    export::save_screenshot(width, height, mandelbrot_renderer.rgba_buffer(), view);
+                }
             }
 
             // Reset events
-            if input.key_pressed(VirtualKeyCode::R) {
-                mandelbrot_renderer.resize(WIDTH as usize, HEIGHT as usize);
-                mandelbrot_renderer.generator = MandelbrotGenerator::new(WIDTH as usize, HEIGHT as usize, MandelbrotGenerator::DEFAULT_MAX_ITERATIONS);
-                mandelbrot_renderer.palette = MandelbrotRenderer::rainbow_palette(MandelbrotGenerator::DEFAULT_MAX_ITERATIONS as usize);
+            if input.key_pressed(keys.reset_view) {
+                mandelbrot_renderer.reset_view();
+            }
+            if input.key_pressed(keys.reset) {
+                // Keep the current size, the pixel buffer only changes with the window
+                let (width, height) = mandelbrot_renderer.dimensions();
+                mandelbrot_renderer.set_render_scale(1);
+                mandelbrot_renderer.resize(width, height);
+                mandelbrot_renderer.generator = MandelbrotGenerator::new(width, height, MandelbrotGenerator::DEFAULT_MAX_ITERATIONS);
+                mandelbrot_renderer.set_builtin_palette(PaletteKind::Rainbow);
             }
 
-            // Resize the window
-            if let Some(size) = input.window_resized() {
-                pixels.resize_surface(size.width, size.height);
-                pixels.resize_buffer(size.width, size.height);
+            // Keep the title on the view, only touching it when the text changes
+            let new_title = mandelbrot_renderer.title();
+            if new_title != title {
+                set_title(&window, &new_title);
+                title = new_title;
+            }
 
-                mandelbrot_renderer.resize(size.width as usize, size.height as usize);
+            // Keep the URL on the view once it settles, rewriting it on every frame of a zoom would be wasted work
+            #[cfg(target_arch = "wasm32")]
+            if !mandelbrot_renderer.is_busy() {
+                let hash = permalink::encode(&mandelbrot_renderer.location());
+                if hash != url_hash {
+                    permalink::write(&hash);
+                    url_hash = hash;
+                }
+            }
+
+            // Redraw continuously while something is changing, otherwise only when input arrives
+            *control_flow = if mandelbrot_renderer.is_busy()
+                || mandelbrot_renderer.is_cycling_palette()
+                || explorer.active
+                || momentum.is_gliding()
+            {
+                ControlFlow::Poll
+            } else {
+                ControlFlow::Wait
+            };
+            // Gamepads don't wake the event loop, so it checks on them every so often while one is plugged in
+            #[cfg(all(feature = "gamepad", not(target_arch = "wasm32")))]
+            if gamepad.is_moving() {
+                *control_flow = ControlFlow::Poll;
+            } else if *control_flow == ControlFlow::Wait && gamepad.is_connected() {
+                *control_flow = ControlFlow::WaitUntil(Instant::now() + GAMEPAD_POLL_INTERVAL);
             }
 
             window.request_redraw();
         }
     });
 }
+
+// The canvas has no title bar on the web, so the page title gets it as well.
+fn set_title(window: &winit::window::Window, title: &str) {
+    window.set_title(title);
+
+    #[cfg(target_arch = "wasm32")]
+    if let Some(document) = web_sys::window().and_then(|win| win.document()) {
+        document.set_title(title);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(clap::Parser)]
+#[command(about = "Explore the Mandelbrot set")]
+#[command(group(clap::ArgGroup::new("batch").args(["headless", "zoom_movie"])))]
+struct Args {
+    /// Window width in pixels [default: 640]
+    #[arg(long)]
+    width: Option<u32>,
+
+    /// Window height in pixels [default: 480]
+    #[arg(long)]
+    height: Option<u32>,
+
+    /// Left edge of the view on the real axis
+    #[arg(long, allow_negative_numbers = true)]
+    x_min: Option<f64>,
+
+    /// Right edge of the view on the real axis
+    #[arg(long, allow_negative_numbers = true)]
+    x_max: Option<f64>,
+
+    /// Top edge of the view on the imaginary axis
+    #[arg(long, allow_negative_numbers = true)]
+    y_min: Option<f64>,
+
+    /// Bottom edge of the view on the imaginary axis
+    #[arg(long, allow_negative_numbers = true)]
+    y_max: Option<f64>,
+
+    /// Two opposite corners of the view as x0,y0,x1,y1, overrides the individual edges
+    #[arg(long, allow_hyphen_values = true, value_parser = parse_bounds)]
+    bounds: Option<(f64, f64, f64, f64)>,
+
+    /// Iteration cap to start with
+    #[arg(long, default_value_t = MandelbrotGenerator::DEFAULT_MAX_ITERATIONS)]
+    max_iterations: f64,
+
+    /// Palette to start with: rainbow, grayscale, fire, ocean, classic or random
+    #[arg(long, value_parser = parse_palette)]
+    palette: Option<config::PaletteChoice>,
+
+    /// Render a single frame to --out and exit without opening a window
+    #[arg(long, requires = "out")]
+    headless: bool,
+
+    /// Render --frames frames zooming in on --target to numbered PNGs in the --out directory, then exit without opening
+    /// a window
+    #[arg(long, requires_all = ["out", "target"])]
+    zoom_movie: bool,
+
+    /// Point the zoom movie closes in on as re,im, kept at the center of every frame
+    #[arg(long, allow_hyphen_values = true, value_parser = parse_target, requires = "zoom_movie")]
+    target: Option<(DoubleDouble, DoubleDouble)>,
+
+    /// Number of frames in the zoom movie
+    #[arg(long, default_value_t = 100, requires = "zoom_movie")]
+    frames: usize,
+
+    /// Fraction of the view's width kept from one zoom movie frame to the next
+    #[arg(long, default_value_t = export::ZOOM_MOVIE_FACTOR, value_parser = parse_frame_zoom, requires = "zoom_movie")]
+    frame_zoom: f64,
+
+    /// PNG file written by --headless, or the directory --zoom-movie writes its frames to
+    #[arg(long, requires = "batch")]
+    out: Option<std::path::PathBuf>,
+
+    /// Reference PNG the --headless render has to match, exiting with an error if it doesn't
+    #[arg(long, requires = "headless")]
+    compare: Option<std::path::PathBuf>,
+
+    /// Largest difference in any color channel of a pixel that still matches the --compare reference
+    #[arg(long, default_value_t = 2, requires = "compare")]
+    tolerance: u8,
+
+    /// Gallery of saved screenshots to use as the bookmarks for this session, numbered from the newest
+    #[arg(long)]
+    gallery: Option<std::path::PathBuf>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Args {
+    // Fills in whatever wasn't given on the command line from the config file.
+    fn with_config(self, config: &config::Config) -> Args {
+        Args {
+            width: self.width.or(config.width),
+            height: self.height.or(config.height),
+            x_min: self.x_min.or(config.x_min),
+            x_max: self.x_max.or(config.x_max),
+            y_min: self.y_min.or(config.y_min),
+            y_max: self.y_max.or(config.y_max),
+            palette: self.palette.or(config.palette),
+            ..self
+        }
+    }
+
+    // Where to save the frame when running headless, None when a window should open.
+    fn headless_output(&self) -> Option<&std::path::Path> {
+        self.out.as_deref().filter(|_| self.headless)
+    }
+
+    // Where to save the frames of a zoom movie and the point to zoom in on, None unless one was asked for.
+    fn zoom_movie(&self) -> Option<(&std::path::Path, (DoubleDouble, DoubleDouble))> {
+        self.out.as_deref().zip(self.target).filter(|_| self.zoom_movie)
+    }
+
+    // Applies whichever edges were given, keeping the generator's defaults for the rest.
+    fn apply_bounds(&self, generator: &mut MandelbrotGenerator<DoubleDouble>) {
+        if let Some((x0, y0, x1, y1)) = self.bounds {
+            // set_bounds widens one axis to keep pixels square, so corners of another shape give a roomier view.
+            let (width, height) = generator.dimensions();
+            let (requested, rendered) = (((x1 - x0) / (y1 - y0)).abs(), width as f64 / height as f64);
+            if (requested / rendered - 1.0).abs() > 1e-3 {
+                warn!(
+                    "--bounds has an aspect ratio of {:.4} but the render {:.4}, widening the view to match",
+                    requested, rendered
+                );
+            }
+            generator.set_bounds(x0.into(), x1.into(), y0.into(), y1.into());
+        } else if self.x_min.is_some() || self.x_max.is_some() || self.y_min.is_some() || self.y_max.is_some() {
+            let bounds = generator.bounds();
+            let edge = |value: Option<f64>, default| value.map_or(default, DoubleDouble::from);
+            generator.set_bounds(
+                edge(self.x_min, bounds.x_min),
+                edge(self.x_max, bounds.x_max),
+                edge(self.y_min, bounds.y_min),
+                edge(self.y_max, bounds.y_max),
+            );
+        }
+    }
+}
+
+// Parses `--target re,im` to full double-double precision, so movies can zoom past f64.
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_target(value: &str) -> Result<(DoubleDouble, DoubleDouble), String> {
+    let parts: Option<Vec<DoubleDouble>> = value
+        .split(',')
+        .map(|part| DoubleDouble::from_str_radix(part.trim(), 10).ok().filter(|part| part.is_finite()))
+        .collect();
+    match parts.as_deref() {
+        Some(&[re, im]) => Ok((re, im)),
+        _ => Err("expected two numbers re,im".to_string()),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_palette(value: &str) -> Result<config::PaletteChoice, String> {
+    config::PaletteChoice::try_from(value.to_string())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_frame_zoom(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(factor) if factor > 0.0 && factor < 1.0 => Ok(factor),
+        _ => Err("expected a fraction between 0 and 1".to_string()),
+    }
+}
+
+// Parses `--bounds x0,y0,x1,y1` from the command line, the two opposite corners of the view in the complex plane.
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_bounds(value: &str) -> Result<(f64, f64, f64, f64), String> {
+    let corners: Result<Vec<f64>, _> = value.split(',').map(|v| v.trim().parse::<f64>()).collect();
+    match corners.as_deref() {
+        Ok(&[x0, y0, x1, y1]) if [x0, y0, x1, y1].iter().all(|v| v.is_finite()) && x0 != x1 && y0 != y1 => {
+            Ok((x0, y0, x1, y1))
+        }
+        _ => Err("expected four finite numbers x0,y0,x1,y1 with distinct corners".to_string()),
+    }
+}
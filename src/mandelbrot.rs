@@ -1,317 +1,4448 @@
-use palette::{Gradient, LinSrgb};
-use rand::Rng;
+use crate::buddhabrot::Buddhabrot;
+use crate::double_double::DoubleDouble;
+use crate::history::ViewHistory;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::worker::{DensityWorker, RenderWorker};
+use instant::{Duration, Instant};
+use num_traits::Float;
+use palette::{Clamp, FromColor, Gradient, Lab, LinSrgb, Oklab, Srgb};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::ops::Range;
 
-fn normalize(n: f64, r_min: f64, r_max: f64, t_min: f64, t_max: f64) -> f64 {
+// Maps n from the range r_min..r_max linearly onto t_min..t_max, extrapolating outside it. An empty source range, like
+// the pixel span of a one pixel wide render, maps everything to the middle of the target instead of dividing by zero.
+fn normalize<F: Float>(n: F, r_min: F, r_max: F, t_min: F, t_max: F) -> F {
+    if r_max == r_min {
+        return (t_min + t_max) / float(2.0);
+    }
     (((n - r_min) / (r_max - r_min)) * (t_max - t_min)) + t_min
 }
 
-pub(crate) struct MandelbrotGenerator {
-    width: usize,
-    height: usize,
-    max_iterations: f64,
-    x_scale_min: f64,
-    x_scale_max: f64,
-    y_scale_min: f64,
-    y_scale_max: f64,
-    iteration_counts: Vec<Vec<f64>>,
-    current_x: usize,
-    current_y: usize,
-    recalculate: bool,
+// Exterior distance estimate |z| ln|z| / |dz|, from the squared magnitudes of the final orbit point and derivative.
+fn distance_estimate(z2: f64, dz2: f64) -> f64 {
+    let z = z2.sqrt();
+    z * z.ln() / dz2.sqrt()
 }
 
-impl MandelbrotGenerator {
-    pub const DEFAULT_MAX_ITERATIONS: f64 = 1000.0;
+// Argument of z/dz, the direction on the plane the potential around the set rises in, from the final orbit point and
+// its derivative. Taken from z times the conjugate of dz, which points the same way.
+fn normal_angle(x: f64, y: f64, dx: f64, dy: f64) -> f64 {
+    (y * dx - x * dy).atan2(x * dx + y * dy)
+}
 
-    pub(crate) fn new(width: usize, height: usize, max_iterations: f64) -> MandelbrotGenerator {
-        MandelbrotGenerator {
-            width,
-            height,
-            max_iterations,
-            x_scale_min: -2.00,
-            x_scale_max: 0.47,
-            y_scale_min: -1.12,
-            y_scale_max: 1.12,
-            iteration_counts: vec![vec![0.0; width]; height],
-            current_x: 0,
-            current_y: 0,
-            recalculate: true,
-        }
+// One term of the stripe average, 1/2 + sin(frequency arg z)/2, between 0 and 1.
+fn stripe_term(frequency: f64, x: f64, y: f64) -> f64 {
+    0.5 + 0.5 * (frequency * y.atan2(x)).sin()
+}
+
+// Stripe average of an escaped orbit from the sum of its terms, one per step, and the last of them. The averages with
+// and without the escaping step are blended by nu, the same amount the smoothed count moves by, so the average carries
+// on continuously across the bands where the step count changes.
+fn stripe_average(sum: f64, last: f64, steps: f64, nu: f64) -> f64 {
+    if steps < 2.0 {
+        return sum;
     }
+    let nu = nu.clamp(0.0, 1.0);
+    nu * sum / steps + (1.0 - nu) * (sum - last) / (steps - 1.0)
+}
 
+// Converts an f64 into the generator's float type, rounding to its precision.
+fn float<F: Float>(value: f64) -> F {
+    F::from(value).expect("every f64 converts to a float type")
+}
 
-    fn x_range(&self) -> f64 {
-        (self.x_scale_max - self.x_scale_min).abs()
-    }
+// The other way around, for everything outside the escape loop that works in f64.
+fn to_f64<F: Float>(value: F) -> f64 {
+    value.to_f64().expect("every float type converts to f64")
+}
 
-    fn y_range(&self) -> f64 {
-        (self.y_scale_max - self.y_scale_min).abs()
+// Raises x + iy to an integer power by repeated complex multiplication.
+fn complex_pow<F: Float>(x: F, y: F, power: u32) -> (F, F) {
+    let mut result = (x, y);
+    for _ in 1..power {
+        result = (result.0 * x - result.1 * y, result.0 * y + result.1 * x);
     }
+    result
+}
 
-    pub fn resize(&mut self, width: usize, height: usize) {
-        self.resize_scaling_factors(width, height);
-        self.width = width;
-        self.height = height;
-        self.iteration_counts = vec![vec![0.0; width]; height];
-        self.recalculate();
-    }
+/// The region of the complex plane covered by the view, in the generator's float type.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds<F = f64> {
+    pub x_min: F,
+    pub x_max: F,
+    pub y_min: F,
+    pub y_max: F,
+}
 
-    fn resize_scaling_factors(&mut self, width: usize, height: usize) {
-        let x_ratio = width as f64 / self.width as f64;
-        let y_ratio = height as f64 / self.height as f64;
+/// Everything needed to return to a location: the view on the complex plane and the iteration cap it was explored at.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ViewParams<F = f64> {
+    pub x_min: F,
+    pub x_max: F,
+    pub y_min: F,
+    pub y_max: F,
+    pub max_iterations: f64,
+}
 
-        let x_range = self.x_range();
-        let y_range = self.y_range();
+/// The smooth escape counts of a frame, read back from [`MandelbrotGenerator::export_iterations`]. Interior pixels
+/// hold exactly `max_iterations`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IterationGrid {
+    pub width: usize,
+    pub height: usize,
+    pub max_iterations: f64,
+    /// One count per pixel, in row-major order.
+    pub iterations: Vec<f32>,
+}
 
-        let new_x_range_diff = (x_ratio * x_range) - x_range;
-        let new_y_range_diff = (y_ratio * y_range) - y_range;
+impl IterationGrid {
+    // Identifies the format and its version, bumped if the layout ever changes.
+    const MAGIC: [u8; 4] = *b"VOM1";
 
-        self.x_scale_min -= new_x_range_diff / 2.0;
-        self.x_scale_max += new_x_range_diff / 2.0;
+    /// Reads a grid in the format `export_iterations` writes.
+    pub fn read<R: Read>(mut reader: R) -> io::Result<IterationGrid> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
 
-        self.y_scale_min -= new_y_range_diff / 2.0;
-        self.y_scale_max += new_y_range_diff / 2.0;
-    }
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != IterationGrid::MAGIC {
+            return Err(invalid("not an iteration grid"));
+        }
 
-    pub(crate) fn zoom(&mut self, coords: (f32, f32), factor: f64) {
-        let x_range = self.x_range();
-        let y_range = self.y_range();
+        let mut size = [0u8; 4];
+        reader.read_exact(&mut size)?;
+        let width = u32::from_le_bytes(size) as usize;
+        reader.read_exact(&mut size)?;
+        let height = u32::from_le_bytes(size) as usize;
+        let mut cap = [0u8; 8];
+        reader.read_exact(&mut cap)?;
+        let max_iterations = f64::from_le_bytes(cap);
 
-        let new_x_range = x_range * factor;
-        let new_y_range = y_range * factor;
+        let bytes = width
+            .checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(4))
+            .ok_or_else(|| invalid("grid size overflows"))?;
+        let mut data = Vec::new();
+        reader.take(bytes as u64).read_to_end(&mut data)?;
+        if data.len() != bytes {
+            return Err(invalid("grid is shorter than its size says"));
+        }
 
-        let new_midpoint_x = normalize(
-            coords.0 as f64,
-            0.0,
-            self.width as f64,
-            self.x_scale_min,
-            self.x_scale_max,
-        );
-        let new_midpoint_y = normalize(
-            coords.1 as f64,
-            0.0,
-            self.height as f64,
-            self.y_scale_min,
-            self.y_scale_max,
-        );
+        let iterations = data
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+        Ok(IterationGrid { width, height, max_iterations, iterations })
+    }
+}
 
-        self.x_scale_min = new_midpoint_x - (new_x_range / 2.0);
-        self.x_scale_max = new_midpoint_x + (new_x_range / 2.0);
+impl<F: Float> Bounds<F> {
+    // Interpolates between two views. The center moves linearly while the extent changes geometrically, so a zoom
+    // appears to progress at a constant rate.
+    fn interpolate(&self, target: &Bounds<F>, t: f64) -> Bounds<F> {
+        let (t, two) = (float::<F>(t), float::<F>(2.0));
+        let center = |min: F, max: F| (min + max) / two;
+        let lerp = |a: F, b: F| a + (b - a) * t;
+        let geometric = |a: F, b: F| a * (b / a).powf(t);
 
-        self.y_scale_min = new_midpoint_y - (new_y_range / 2.0);
-        self.y_scale_max = new_midpoint_y + (new_y_range / 2.0);
+        let x_center = lerp(center(self.x_min, self.x_max), center(target.x_min, target.x_max));
+        let y_center = lerp(center(self.y_min, self.y_max), center(target.y_min, target.y_max));
+        let x_range = geometric(self.x_max - self.x_min, target.x_max - target.x_min);
+        let y_range = geometric(self.y_max - self.y_min, target.y_max - target.y_min);
 
-        self.recalculate();
+        Bounds {
+            x_min: x_center - x_range / two,
+            x_max: x_center + x_range / two,
+            y_min: y_center - y_range / two,
+            y_max: y_center + y_range / two,
+        }
     }
 
-    pub fn recalculate(&mut self) {
-        self.recalculate = true;
+    // Rounds the bounds to another float type.
+    fn cast<T: Float>(&self) -> Bounds<T> {
+        Bounds {
+            x_min: float(to_f64(self.x_min)),
+            x_max: float(to_f64(self.x_max)),
+            y_min: float(to_f64(self.y_min)),
+            y_max: float(to_f64(self.y_max)),
+        }
     }
+}
 
-    fn test_pixel(&self, px: u32, py: u32) -> f64 {
-        let x0 = normalize(
-            px as f64,
-            0.0,
-            (self.width - 1) as f64,
-            self.x_scale_min,
-            self.x_scale_max,
-        );
+// An in-progress animated zoom between two views.
+struct AnimationState {
+    start: Bounds<DoubleDouble>,
+    target: Bounds<DoubleDouble>,
+    started: Instant,
+}
 
-        let y0 = normalize(
-            py as f64,
-            0.0,
-            (self.height - 1) as f64,
-            self.y_scale_min,
-            self.y_scale_max,
-        );
+// An in-progress morph from a formula into the generator's current one.
+struct FormulaMorph {
+    from: Formula,
+    started: Instant,
+}
 
-        let mut x: f64 = 0.0;
-        let mut y: f64 = 0.0;
-        let mut x2: f64 = 0.0;
-        let mut y2: f64 = 0.0;
+// An in-progress cross-fade between two expanded palettes.
+struct PaletteFade {
+    from: Vec<LinSrgb>,
+    to: Vec<LinSrgb>,
+    started: Instant,
+}
 
-        let mut iteration = 0.0;
+/// Optional post-zoom adjustment that lands the magnification on a clean power, 10x, 100x or 2x, 4x and so on, making
+/// views easy to share.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ZoomSnap {
+    Off,
+    PowerOfTen,
+    PowerOfTwo,
+}
 
-        // Cardioid checking
+impl ZoomSnap {
+    pub fn next(self) -> Self {
+        match self {
+            ZoomSnap::Off => ZoomSnap::PowerOfTen,
+            ZoomSnap::PowerOfTen => ZoomSnap::PowerOfTwo,
+            ZoomSnap::PowerOfTwo => ZoomSnap::Off,
+        }
+    }
+
+    // Snaps a magnification to a power of the base, rounding in the zoom direction so a step can never be undone by
+    // snapping back to where it started.
+    fn snap(self, magnification: f64, zooming_in: bool) -> Option<f64> {
+        let base: f64 = match self {
+            ZoomSnap::Off => return None,
+            ZoomSnap::PowerOfTen => 10.0,
+            ZoomSnap::PowerOfTwo => 2.0,
+        };
+
+        // Tolerate float noise on magnifications that are already a clean power
+        let exponent = magnification.log(base);
+        let exponent = if (exponent - exponent.round()).abs() < 1e-9 {
+            exponent.round()
+        } else if zooming_in {
+            exponent.ceil()
+        } else {
+            exponent.floor()
+        };
+
+        Some(base.powf(exponent))
+    }
+}
+
+/// Which part of the set's anatomy a pixel belongs to. Only the components with exact algebraic membership tests are
+/// identified, any other bounded orbit is lumped together.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Component {
+    Exterior,
+    MainCardioid,
+    Period2Bulb,
+    OtherInterior,
+}
+
+impl Component {
+    // Tests a point against the main cardioid and the period-2 bulb.
+    pub(crate) fn classify(x0: f64, y0: f64) -> Option<Component> {
         let y0_2 = y0 * y0;
         let p = ((x0 - 0.25).powf(2.0) + y0_2).sqrt();
 
-        let is_large_cardioid = x0 <= p - 2.0 * p * p + 0.25;
-        let is_period_2_bulb = (x0 + 1.0).powf(2.0) + y0_2 <= 1.0 / 16.0;
-
-        if is_large_cardioid || is_period_2_bulb {
-            return self.max_iterations;
+        if x0 <= p - 2.0 * p * p + 0.25 {
+            Some(Component::MainCardioid)
+        } else if (x0 + 1.0).powf(2.0) + y0_2 <= 1.0 / 16.0 {
+            Some(Component::Period2Bulb)
+        } else {
+            None
         }
+    }
+}
 
-        let mut x_old = 0.0;
-        let mut y_old = 0.0;
-        let mut period = 0;
+/// Whether the pixel picks the parameter c, or the starting point z with c fixed.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum FractalType {
+    Mandelbrot,
+    Julia,
+}
 
-        // Escape algorithm
-        while ((x2 + y2) <= 4.0) && iteration < self.max_iterations {
-            y = 2.0 * x * y + y0;
-            x = x2 - y2 + x0;
-            x2 = x * x;
-            y2 = y * y;
+/// The recurrence iterated for every point.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Formula {
+    /// z = z^2 + c
+    Mandelbrot,
+    /// z = conj(z)^2 + c, also known as the Mandelbar
+    Tricorn,
+    /// z = (|x| + i|y|)^2 + c
+    BurningShip,
+}
 
-            iteration += 1.0;
+impl Formula {
+    pub fn next(self) -> Self {
+        match self {
+            Formula::Mandelbrot => Formula::Tricorn,
+            Formula::Tricorn => Formula::BurningShip,
+            Formula::BurningShip => Formula::Mandelbrot,
+        }
+    }
+}
 
-            // Periodicity checking
-            if x == x_old && y == y_old {
-                return self.max_iterations;
-            }
+/// What the generator knows about a pixel after running the escape algorithm.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PixelValue {
+    /// Smoothed escape count, stored in single precision since it's only used for coloring.
+    pub iterations: f32,
+    /// Argument of the final orbit point, in radians, for escaped pixels.
+    pub escape_angle: f64,
+    /// Estimated distance to the set on the complex plane for escaped pixels, zero for interior ones.
+    pub distance: f64,
+    /// Stripe average of the orbit, between 0 and 1, for escaped pixels while the generator has a stripe frequency.
+    pub stripe: f64,
+    /// Argument of z/dz at the escape point, in radians, the direction of the surface normal normal map shading uses.
+    pub normal_angle: f64,
+    pub component: Component,
+}
 
-            period += 1;
-            if period > 20 {
-                period = 0;
-                x_old = x;
-                y_old = y;
-            }
+impl PixelValue {
+    fn interior(max_iterations: f64, component: Component) -> Self {
+        PixelValue {
+            iterations: max_iterations as f32,
+            escape_angle: 0.0,
+            distance: 0.0,
+            stripe: 0.0,
+            normal_angle: 0.0,
+            component,
         }
+    }
 
-        if iteration < self.max_iterations {
-            let log_zn = (x2 + y2).log10();
-            let log_2 = 2.0_f64.log10();
-            let nu = (log_zn / log_2).log10() / log_2;
-            iteration = iteration + 1.0 - nu;
+    // The value of the complex conjugate point, whose orbit is the conjugate of this one. Negating every argument
+    // mirrors the stripe terms about one half.
+    fn conjugate(self) -> Self {
+        PixelValue {
+            escape_angle: -self.escape_angle,
+            normal_angle: -self.normal_angle,
+            stripe: if self.component == Component::Exterior { 1.0 - self.stripe } else { self.stripe },
+            ..self
         }
+    }
+}
+
+// Orbit state of a pixel that had not escaped when its pass hit the iteration cap, kept so a later pass can continue
+// from where it stopped instead of starting over.
+#[derive(Clone, Copy)]
+struct EscapeState<F> {
+    x: F,
+    y: F,
+    // Derivative of the orbit with respect to the pixel, for the distance estimate.
+    dx: F,
+    dy: F,
+    iteration: f64,
+    // Running sum of the stripe terms and the latest of them, see `stripe_average`.
+    stripe_sum: f64,
+    stripe_last: f64,
+}
 
-        iteration
+impl<F: Float> EscapeState<F> {
+    // Moves the state to another float type. The orbit of a pixel is always continued in the type that started it,
+    // so this only ever passes through values the target type can already hold.
+    fn cast<T: Float>(self) -> EscapeState<T> {
+        EscapeState {
+            x: float(to_f64(self.x)),
+            y: float(to_f64(self.y)),
+            dx: float(to_f64(self.dx)),
+            dy: float(to_f64(self.dy)),
+            iteration: self.iteration,
+            stripe_sum: self.stripe_sum,
+            stripe_last: self.stripe_last,
+        }
     }
 }
 
-impl Iterator for MandelbrotGenerator {
-    type Item = f64;
+// Orbit of a single point computed in the generator's full precision and rounded to f64 at every step. Pixels around
+// it only iterate their small offset from it, which f64 holds fine even where their coordinates no longer fit in it.
+struct ReferenceOrbit<F> {
+    cx: F,
+    cy: F,
+    // z_n for n = 0 until the orbit escapes or hits the iteration cap, inclusive.
+    points: Vec<(f64, f64)>,
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let x = self.current_x;
-        let y = self.current_y;
+impl<F: Float> ReferenceOrbit<F> {
+    fn new(cx: F, cy: F, max_iterations: f64, bailout: f64) -> Self {
+        let bailout = float::<F>(bailout);
+        let two = float::<F>(2.0);
+        let (mut x, mut y) = (F::zero(), F::zero());
+        let mut points = vec![(0.0, 0.0)];
 
-        if self.recalculate {
-            self.iteration_counts[y][x] = self.test_pixel(x as u32, y as u32);
+        while x * x + y * y <= bailout && (points.len() as f64) <= max_iterations {
+            (x, y) = (x * x - y * y + cx, two * x * y + cy);
+            points.push((to_f64(x), to_f64(y)));
         }
 
-        self.current_x += 1;
+        ReferenceOrbit { cx, cy, points }
+    }
+}
 
-        if self.current_x >= self.width {
-            self.current_x = 0;
-            self.current_y += 1;
-        }
+/// Computes escape counts for every pixel of a view on the complex plane. Iterating it hands out the pixels in
+/// row-major order, doing as much work per pixel as the current pass needs and wrapping around after the last one.
+///
+/// The view bounds and the escape loop use the float type `F`. `f32` is faster where precision allows, and the
+/// default `f64` holds up to magnifications of around 10^13.
+pub struct MandelbrotGenerator<F: Float = f64> {
+    width: usize,
+    height: usize,
+    max_iterations: f64,
+    x_scale_min: F,
+    x_scale_max: F,
+    y_scale_min: F,
+    y_scale_max: F,
+    // Per-pixel buffers, flat in row-major order, see `index`.
+    iteration_counts: Vec<f32>,
+    escape_angles: Vec<f64>,
+    distances: Vec<f64>,
+    stripes: Vec<f64>,
+    normal_angles: Vec<f64>,
+    components: Vec<Component>,
+    escape_states: Vec<Option<EscapeState<F>>>,
+    current_x: usize,
+    current_y: usize,
+    recalculate: bool,
+    refine: bool,
+    // Bumped by every `recalculate`, tells frames computed elsewhere from a copy apart from the current one.
+    revision: u64,
+    // Tiles of the current sweep computed so far by `compute_tiles`, which works through them in row-major order.
+    tiles_done: usize,
+    // Set when the current sweep was computed up front by a whole-frame path.
+    bulk_sweep: bool,
+    // Fraction of a pixel panned but not yet applied, see `pan`.
+    pan_remainder: (f64, f64),
+    // Cumulative fraction of escaped pixels at or below each integer iteration count, rebuilt once a recompute
+    // completes. Empty until then.
+    histogram: Vec<f32>,
+    /// Iteration cap of the quick first pass run after every recalculation. Pixels still bounded at this cap are shown
+    /// as interior until the refinement pass continues them up to `max_iterations`.
+    pub preview_iterations: f64,
+    pub zoom_snap: ZoomSnap,
+    // Horizontal extent of the view at construction, the reference for zoom depth.
+    initial_x_range: F,
+    // Grow `max_iterations` with zoom depth.
+    auto_iterations: bool,
+    auto_iterations_base: f64,
+    fractal_type: FractalType,
+    formula: Formula,
+    // A formula being morphed away from and how far along, see `set_morph`.
+    morph: Option<(Formula, f64)>,
+    // Exponent d of the Multibrot recurrence z = z^d + c.
+    power: u32,
+    // The fixed parameter c used in Julia mode.
+    julia_c: (f64, f64),
+    // Frequency of the stripe average gathered along each orbit, None to skip the extra work.
+    stripe_frequency: Option<f64>,
+    /// Compute the bulk of the frame in f32 and only redo pixels near the boundary in f64.
+    pub hybrid_precision: bool,
+    /// Compute views that need more than f64 by perturbation against a reference orbit, see `compute_perturbed`.
+    pub perturbation: bool,
+    // Samples taken per pixel, see `SUPERSAMPLE_OFFSETS`.
+    aa_samples: usize,
+    // Squared escape radius. The renormalized count assumes |z| squares from one step to the next, which c spoils
+    // near a small radius, leaving faint seams between the bands. A larger radius removes them and makes the
+    // distance estimate more accurate too.
+    bailout: f64,
+    /// Iterations between updates of the point the orbit is compared against for periodicity, the longest cycle that
+    /// can be caught.
+    pub periodicity_interval: usize,
+    /// How close, as a fraction of the pixel width, the orbit has to come back to that point to count as periodic and
+    /// end as interior. Zero only catches exact repeats.
+    pub periodicity_epsilon: f64,
+    /// Skip iterating points inside the main cardioid and period-2 bulb. Only ever applies to the standard Mandelbrot
+    /// set, turning it off there should leave the picture unchanged and only cost time.
+    pub use_cardioid_check: bool,
+}
 
-        if self.current_y >= self.height {
-            self.current_y = 0;
-            self.current_x = 0;
-            self.recalculate = false;
-        }
+/// Why [`MandelbrotGeneratorBuilder::build`] refused its settings.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BuildError {
+    /// The render size has no pixels.
+    EmptySize { width: usize, height: usize },
+    /// The squared escape radius isn't a positive finite number.
+    InvalidBailout(f64),
+    /// There are no `SUPERSAMPLE_OFFSETS` for this many samples per pixel.
+    UnsupportedSamples(usize),
+}
 
-        Some(self.iteration_counts[y][x])
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::EmptySize { width, height } => write!(f, "render size {}x{} has no pixels", width, height),
+            BuildError::InvalidBailout(bailout) => write!(f, "bailout {} isn't positive and finite", bailout),
+            BuildError::UnsupportedSamples(samples) => write!(f, "{} samples per pixel aren't supported", samples),
+        }
     }
 }
 
-pub(crate) struct MandelbrotRenderer {
-    pub(crate) generator: MandelbrotGenerator,
+impl std::error::Error for BuildError {}
+
+/// Settings for a new [`MandelbrotGenerator`] beyond the ones `new` takes, see [`MandelbrotGenerator::builder`].
+/// Anything left unset keeps the default `new` would use.
+#[derive(Clone, Copy, Debug)]
+pub struct MandelbrotGeneratorBuilder<F: Float = f64> {
     width: usize,
     height: usize,
-    pub(crate) palette: Vec<LinSrgb>,
-    redraw: bool,
-    frame_buffer: Vec<u8>,
+    max_iterations: f64,
+    bounds: Option<Bounds<F>>,
+    formula: Formula,
+    bailout: f64,
+    aa_samples: usize,
 }
 
-impl MandelbrotRenderer {
-    pub(crate) fn new(width: usize, height: usize, generator: MandelbrotGenerator) -> Self {
-        MandelbrotRenderer {
-            generator,
+impl<F: Float + Send + Sync> MandelbrotGeneratorBuilder<F> {
+    /// The initial view, widened along one axis if needed to keep pixels square like `set_bounds`. Magnification
+    /// stays relative to the whole set.
+    pub fn bounds(mut self, bounds: Bounds<F>) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    pub fn max_iterations(mut self, max_iterations: f64) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    pub fn formula(mut self, formula: Formula) -> Self {
+        self.formula = formula;
+        self
+    }
+
+    /// The squared escape radius, raised to `MIN_BAILOUT` if it's below it.
+    pub fn bailout(mut self, bailout: f64) -> Self {
+        self.bailout = bailout;
+        self
+    }
+
+    /// Samples per pixel, 1, 2 or 4.
+    pub fn aa_samples(mut self, samples: usize) -> Self {
+        self.aa_samples = samples;
+        self
+    }
+
+    pub fn build(self) -> Result<MandelbrotGenerator<F>, BuildError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(BuildError::EmptySize { width: self.width, height: self.height });
+        }
+        if !(self.bailout.is_finite() && self.bailout > 0.0) {
+            return Err(BuildError::InvalidBailout(self.bailout));
+        }
+        if !MandelbrotGenerator::SUPERSAMPLE_OFFSETS.iter().any(|offsets| offsets.len() == self.aa_samples) {
+            return Err(BuildError::UnsupportedSamples(self.aa_samples));
+        }
+        Ok(self.assemble())
+    }
+
+    // The generator without any checks, `new` is allowed an empty size.
+    fn assemble(self) -> MandelbrotGenerator<F> {
+        let (width, height, max_iterations) = (self.width, self.height, self.max_iterations);
+        let mut generator = MandelbrotGenerator {
             width,
             height,
-            palette: MandelbrotRenderer::rainbow_palette(MandelbrotGenerator::DEFAULT_MAX_ITERATIONS as usize),
-            redraw: true,
-            frame_buffer: vec![0xffu8; width * height * 4],
+            max_iterations,
+            x_scale_min: float(MandelbrotGenerator::DEFAULT_X_BOUNDS.0),
+            x_scale_max: float(MandelbrotGenerator::DEFAULT_X_BOUNDS.1),
+            y_scale_min: float(MandelbrotGenerator::DEFAULT_Y_BOUNDS.0),
+            y_scale_max: float(MandelbrotGenerator::DEFAULT_Y_BOUNDS.1),
+            iteration_counts: vec![0.0; width * height],
+            escape_angles: vec![0.0; width * height],
+            distances: vec![0.0; width * height],
+            stripes: vec![0.0; width * height],
+            normal_angles: vec![0.0; width * height],
+            components: vec![Component::Exterior; width * height],
+            escape_states: vec![None; width * height],
+            current_x: 0,
+            current_y: 0,
+            recalculate: true,
+            refine: false,
+            revision: 0,
+            tiles_done: 0,
+            bulk_sweep: false,
+            pan_remainder: (0.0, 0.0),
+            preview_iterations: MandelbrotGenerator::DEFAULT_PREVIEW_ITERATIONS,
+            zoom_snap: ZoomSnap::Off,
+            initial_x_range: float(MandelbrotGenerator::DEFAULT_X_BOUNDS.1 - MandelbrotGenerator::DEFAULT_X_BOUNDS.0),
+            auto_iterations: false,
+            auto_iterations_base: max_iterations,
+            fractal_type: FractalType::Mandelbrot,
+            formula: self.formula,
+            morph: None,
+            power: 2,
+            julia_c: MandelbrotGenerator::DEFAULT_JULIA_C,
+            stripe_frequency: None,
+            hybrid_precision: false,
+            perturbation: true,
+            aa_samples: self.aa_samples,
+            bailout: self.bailout.max(MandelbrotGenerator::MIN_BAILOUT),
+            periodicity_interval: MandelbrotGenerator::DEFAULT_PERIODICITY_INTERVAL,
+            periodicity_epsilon: MandelbrotGenerator::DEFAULT_PERIODICITY_EPSILON,
+            use_cardioid_check: true,
+            histogram: Vec::new(),
+        };
+        generator.enforce_aspect();
+        // Magnification is relative to the view actually shown, after the aspect fix
+        generator.initial_x_range = generator.x_range();
+
+        if let Some(bounds) = self.bounds {
+            generator.x_scale_min = bounds.x_min.min(bounds.x_max);
+            generator.x_scale_max = bounds.x_min.max(bounds.x_max);
+            generator.y_scale_min = bounds.y_min.min(bounds.y_max);
+            generator.y_scale_max = bounds.y_min.max(bounds.y_max);
+            generator.enforce_aspect();
         }
+        generator
+    }
+}
+
+impl MandelbrotGenerator {
+    /// The whole set with a little room around it, widened along one axis to the render's aspect ratio.
+    pub const DEFAULT_X_BOUNDS: (f64, f64) = (-2.00, 0.47);
+    pub const DEFAULT_Y_BOUNDS: (f64, f64) = (-1.12, 1.12);
+    pub const DEFAULT_MAX_ITERATIONS: f64 = 1000.0;
+    pub const DEFAULT_PREVIEW_ITERATIONS: f64 = 50.0;
+    pub const MIN_MAX_ITERATIONS: f64 = 16.0;
+    pub const DEFAULT_JULIA_C: (f64, f64) = (-0.8, 0.156);
+    pub const AUTO_ITERATIONS_PER_DEPTH: f64 = 100.0;
+    pub const MAX_AUTO_ITERATIONS: f64 = 50_000.0;
+    pub const MIN_POWER: u32 = 2;
+    pub const MAX_POWER: u32 = 16;
+    /// Radius 2, the smallest that still catches every escaping orbit.
+    pub const DEFAULT_BAILOUT: f64 = 4.0;
+    pub const MIN_BAILOUT: f64 = 4.0;
+    /// Pixel spacing, in f64 ulps of the view coordinates, below which a generator over a float type wider than f64
+    /// runs its escape loop in that type. Reached at around 10^10 magnification near the main features.
+    pub const EXTENDED_PRECISION_ULPS: f64 = 4096.0;
+    /// A perturbed orbit counts as glitched once its squared magnitude drops below this fraction of the reference's,
+    /// the point where the offset has lost most of its significant digits.
+    pub const GLITCH_TOLERANCE: f64 = 1e-3;
+    /// Reference orbits tried per frame before the pixels still glitched are computed directly.
+    pub const MAX_REFERENCE_ORBITS: usize = 8;
+    pub const DEFAULT_PERIODICITY_INTERVAL: usize = 20;
+    pub const DEFAULT_PERIODICITY_EPSILON: f64 = 1e-3;
+    /// Side length in pixels of the square tiles a sweep is split into, see `compute_tiles`.
+    pub const TILE_SIZE: usize = 64;
+    /// Sub-pixel offsets for each supported sample count. Four samples use a rotated grid, which catches edges at any
+    /// angle better than a square one.
+    pub const SUPERSAMPLE_OFFSETS: [&'static [(f64, f64)]; 3] = [
+        &[(0.0, 0.0)],
+        &[(0.25, 0.25), (0.75, 0.75)],
+        &[(0.375, 0.125), (0.875, 0.375), (0.125, 0.625), (0.625, 0.875)],
+    ];
+}
+
+impl<F: Float + Send + Sync> MandelbrotGenerator<F> {
+    /// A generator showing the whole set at the given render size.
+    pub fn new(width: usize, height: usize, max_iterations: f64) -> MandelbrotGenerator<F> {
+        MandelbrotGenerator::builder(width, height).max_iterations(max_iterations).assemble()
     }
 
-    pub(crate) fn draw(&mut self, frame: &mut [u8]) {
-        if self.redraw {
-            self.redraw = false;
-            self.draw_to_frame_buffer();
+    /// Starts a generator with more settings than `new` takes, checked by `build`.
+    pub fn builder(width: usize, height: usize) -> MandelbrotGeneratorBuilder<F> {
+        MandelbrotGeneratorBuilder {
+            width,
+            height,
+            max_iterations: MandelbrotGenerator::DEFAULT_MAX_ITERATIONS,
+            bounds: None,
+            formula: Formula::Mandelbrot,
+            bailout: MandelbrotGenerator::DEFAULT_BAILOUT,
+            aa_samples: 1,
         }
+    }
 
-        frame.copy_from_slice(&self.frame_buffer);
+    fn x_range(&self) -> F {
+        (self.x_scale_max - self.x_scale_min).abs()
+    }
 
-        self.redraw = false;
+    fn y_range(&self) -> F {
+        (self.y_scale_max - self.y_scale_min).abs()
     }
 
-    fn draw_to_frame_buffer(&mut self) {
-        for pixel in self.frame_buffer.chunks_exact_mut(4) {
-            let mandelbrot_value = self.generator.next().unwrap();
-            let rgba: [u8; 4] = if mandelbrot_value == self.generator.max_iterations {
-                [0, 0, 0, 0xff]
-            } else {
-                let iterations: usize = mandelbrot_value.floor() as usize;
-                let fraction = mandelbrot_value % 1.0;
+    pub fn center(&self) -> (f64, f64) {
+        let two = float::<F>(2.0);
+        (
+            to_f64((self.x_scale_min + self.x_scale_max) / two),
+            to_f64((self.y_scale_min + self.y_scale_max) / two),
+        )
+    }
 
-                let color1 = self.palette[iterations];
-                let color2 = self.palette[iterations + 1];
+    /// The view's real extent as (min, max), rounded to `f64`. `bounds` has it at full precision.
+    pub fn x_bounds(&self) -> (f64, f64) {
+        (to_f64(self.x_scale_min), to_f64(self.x_scale_max))
+    }
 
-                MandelbrotRenderer::color_to_rgba(&Gradient::from([
-                    (0.0, color1),
-                    (1.0, color2)
-                ]).get(fraction as f32))
-            };
+    /// The view's imaginary extent as (min, max), rounded to `f64`.
+    pub fn y_bounds(&self) -> (f64, f64) {
+        (to_f64(self.y_scale_min), to_f64(self.y_scale_max))
+    }
+
+    /// The view center at the generator's own precision, where `center` rounds it to `f64`.
+    pub fn precise_center(&self) -> (F, F) {
+        let two = float::<F>(2.0);
+        ((self.x_scale_min + self.x_scale_max) / two, (self.y_scale_min + self.y_scale_max) / two)
+    }
+
+    /// Width of one pixel on the complex plane.
+    pub fn pixel_size(&self) -> f64 {
+        to_f64(self.x_range()) / self.width.max(1) as f64
+    }
+
+    /// Whether neighboring pixels are closer together than `F` can tell apart at the view's coordinates, past which
+    /// zooming further only shows the image breaking up into blocks. For a plain f64 generator at window sizes this
+    /// happens around 10^13 magnification, double-double takes it to around 10^29.
+    pub fn precision_exhausted(&self) -> bool {
+        self.pixel_size() < self.coordinate_magnitude() * to_f64(F::epsilon())
+    }
+
+    /// How far the view is zoomed in relative to the initial one.
+    pub fn magnification(&self) -> f64 {
+        to_f64(self.initial_x_range / self.x_range())
+    }
+
+    /// Centers the view on a point at a magnification relative to the initial view, the inverse of `center` and
+    /// `magnification`. Magnifications that aren't positive and finite are ignored.
+    pub fn set_center_and_zoom(&mut self, re: F, im: F, magnification: f64) {
+        if !(magnification.is_finite() && magnification > 0.0) {
+            return;
+        }
+
+        let x_range = self.initial_x_range / float(magnification);
+        let y_range = x_range * float(self.height.max(1) as f64 / self.width.max(1) as f64);
+        let two = float::<F>(2.0);
+        self.x_scale_min = re - x_range / two;
+        self.x_scale_max = re + x_range / two;
+        self.y_scale_min = im - y_range / two;
+        self.y_scale_max = im + y_range / two;
 
-            pixel.copy_from_slice(&rgba);
+        self.apply_auto_iterations();
+        self.recalculate();
+    }
+
+    /// A copy of this generator's view and settings at another resolution, computed in a single full-cap pass.
+    pub fn at_resolution(&self, width: usize, height: usize) -> MandelbrotGenerator<F> {
+        MandelbrotGenerator {
+            width,
+            height,
+            iteration_counts: vec![0.0; width * height],
+            escape_angles: vec![0.0; width * height],
+            distances: vec![0.0; width * height],
+            stripes: vec![0.0; width * height],
+            normal_angles: vec![0.0; width * height],
+            components: vec![Component::Exterior; width * height],
+            escape_states: vec![None; width * height],
+            current_x: 0,
+            current_y: 0,
+            recalculate: true,
+            refine: false,
+            tiles_done: 0,
+            bulk_sweep: false,
+            histogram: Vec::new(),
+            preview_iterations: 0.0,
+            ..*self
         }
     }
 
-    pub(crate) fn zoom(&mut self, coords: (f32, f32), factor: f64) {
-        self.generator.zoom(coords, factor);
-        self.redraw = true;
+    // A copy carrying the view and every setting but none of the per-pixel storage, for mapping points and stepping
+    // the recurrence away from the frame.
+    pub(crate) fn without_pixels(&self) -> MandelbrotGenerator<F> {
+        MandelbrotGenerator {
+            width: self.width,
+            height: self.height,
+            ..self.at_resolution(0, 0)
+        }
+    }
+
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    pub fn bounds(&self) -> Bounds<F> {
+        Bounds {
+            x_min: self.x_scale_min,
+            x_max: self.x_scale_max,
+            y_min: self.y_scale_min,
+            y_max: self.y_scale_max,
+        }
+    }
+
+    pub fn view(&self) -> ViewParams<F> {
+        ViewParams {
+            x_min: self.x_scale_min,
+            x_max: self.x_scale_max,
+            y_min: self.y_scale_min,
+            y_max: self.y_scale_max,
+            max_iterations: self.max_iterations,
+        }
+    }
+
+    pub fn set_view(&mut self, view: ViewParams<F>) {
+        self.set_bounds(view.x_min, view.x_max, view.y_min, view.y_max);
+        if view.max_iterations != self.max_iterations {
+            self.set_max_iterations(view.max_iterations);
+        }
+    }
+
+    /// Goes back to the view a new generator starts with, leaving the iteration cap and everything else as it is.
+    pub fn reset_view(&mut self) {
+        self.x_scale_min = float(MandelbrotGenerator::DEFAULT_X_BOUNDS.0);
+        self.x_scale_max = float(MandelbrotGenerator::DEFAULT_X_BOUNDS.1);
+        self.y_scale_min = float(MandelbrotGenerator::DEFAULT_Y_BOUNDS.0);
+        self.y_scale_max = float(MandelbrotGenerator::DEFAULT_Y_BOUNDS.1);
+        self.enforce_aspect();
+        self.recalculate();
+    }
+
+    /// Restores a view as-is, without any of the validation `set_bounds` does.
+    pub fn apply_bounds(&mut self, bounds: Bounds<F>) {
+        self.x_scale_min = bounds.x_min;
+        self.x_scale_max = bounds.x_max;
+        self.y_scale_min = bounds.y_min;
+        self.y_scale_max = bounds.y_max;
+        self.recalculate();
+    }
+
+    /// Sets the view from two opposite corners of the complex plane, in any order. The view is widened along one axis
+    /// if needed to keep pixels square, so the whole requested region stays visible.
+    pub fn set_bounds(&mut self, x_min: F, x_max: F, y_min: F, y_max: F) {
+        self.x_scale_min = x_min.min(x_max);
+        self.x_scale_max = x_min.max(x_max);
+        self.y_scale_min = y_min.min(y_max);
+        self.y_scale_max = y_min.max(y_max);
+        self.enforce_aspect();
+
+        self.recalculate();
     }
 
-    pub(crate) fn resize(&mut self, width: usize, height: usize) {
+    /// Changes the render size, keeping the view's center and the area a pixel covers. A zero width or height, which
+    /// a minimized window reports, is ignored so the view survives it.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        self.resize_scaling_factors(width, height);
         self.width = width;
         self.height = height;
-        self.frame_buffer = vec![0xffu8; width * height * 4];
-        self.generator.resize(width, height);
-        self.redraw = true;
+        self.enforce_aspect();
+        self.iteration_counts = vec![0.0; width * height];
+        self.escape_angles = vec![0.0; width * height];
+        self.distances = vec![0.0; width * height];
+        self.stripes = vec![0.0; width * height];
+        self.normal_angles = vec![0.0; width * height];
+        self.components = vec![Component::Exterior; width * height];
+        self.escape_states = vec![None; width * height];
+        self.recalculate();
     }
 
-    pub(crate) fn randomize_palette(&mut self) {
-        self.palette = MandelbrotRenderer::random_palette(self.generator.max_iterations as usize);
-        self.redraw = true;
+    // Expands the narrower axis of the view about its center until the view has the same aspect ratio as the render,
+    // so a pixel covers a square of the complex plane.
+    fn enforce_aspect(&mut self) {
+        let render_aspect = self.width as f64 / self.height as f64;
+        let x_range = self.x_range();
+        let y_range = self.y_range();
+        if !(render_aspect.is_finite() && render_aspect > 0.0 && x_range > F::zero() && y_range > F::zero()) {
+            return;
+        }
+
+        let render_aspect = float::<F>(render_aspect);
+        let two = float::<F>(2.0);
+        if x_range / y_range < render_aspect {
+            let center = (self.x_scale_min + self.x_scale_max) / two;
+            self.x_scale_min = center - y_range * render_aspect / two;
+            self.x_scale_max = center + y_range * render_aspect / two;
+        } else {
+            let center = (self.y_scale_min + self.y_scale_max) / two;
+            self.y_scale_min = center - x_range / render_aspect / two;
+            self.y_scale_max = center + x_range / render_aspect / two;
+        }
     }
 
-    pub(crate) fn random_palette(n_colors: usize) -> Vec<LinSrgb> {
-        let mut rng = rand::thread_rng();
-        let mut pool: Vec<f32> = vec![0.0; 15];
-        for i in 1..15 {
-            assert!(i < pool.len());
-            pool[i] = rng.gen_range(0.0..1.0)
+    // Scales the view's extent with the render size about its center, so a pixel keeps covering the same area and the
+    // point in the middle of the window stays put. Bounds are rebuilt from the center rather than nudged edge by edge,
+    // which would let rounding walk the center off over a series of resizes.
+    fn resize_scaling_factors(&mut self, width: usize, height: usize) {
+        if self.width == 0 || self.height == 0 {
+            return;
         }
 
-        Gradient::from(vec![
-            (0.0, LinSrgb::new(pool.pop().unwrap(), pool.pop().unwrap(), pool.pop().unwrap())),
-            (0.1, LinSrgb::new(pool.pop().unwrap(), pool.pop().unwrap(), pool.pop().unwrap())),
-            (2.5, LinSrgb::new(pool.pop().unwrap(), pool.pop().unwrap(), pool.pop().unwrap())),
-            (6.0, LinSrgb::new(pool.pop().unwrap(), pool.pop().unwrap(), pool.pop().unwrap())),
-            (10.0, LinSrgb::new(pool.pop().unwrap(), pool.pop().unwrap(), pool.pop().unwrap())),
-        ]).take(n_colors).collect()
-    }
+        let (center_x, center_y) = self.precise_center();
+        let two = float::<F>(2.0);
+        let half_x_range = self.x_range() * float(width as f64 / self.width as f64) / two;
+        let half_y_range = self.y_range() * float(height as f64 / self.height as f64) / two;
 
-    pub(crate) fn rainbow_palette(n_colors: usize) -> Vec<LinSrgb> {
-        Gradient::from(vec![
-            (0.0, LinSrgb::new(1.0, 0.0, 0.0)),
-            (0.05, LinSrgb::new(0.0, 1.0, 0.0)),
-            (0.5, LinSrgb::new(0.0, 0.0, 1.0)),
-            (1.5, LinSrgb::new(0.0, 1.0, 0.0)),
-            (2.5, LinSrgb::new(1.0, 0.0, 0.0)),
-        ]).take(n_colors).collect()
+        self.x_scale_min = center_x - half_x_range;
+        self.x_scale_max = center_x + half_x_range;
+        self.y_scale_min = center_y - half_y_range;
+        self.y_scale_max = center_y + half_y_range;
     }
 
-    fn color_to_rgba(color: &LinSrgb) -> [u8; 4] {
-        [
-            (color.red * 0xff as f32) as u8,
-            (color.green * 0xff as f32) as u8,
-            (color.blue * 0xff as f32) as u8,
-            0xff,
-        ]
+    /// Centers the view on the point under `coords` and scales its extent by `factor`, below one to zoom in. The new
+    /// center is exactly that point, so zooming by `factor` and then by `1 / factor` in the middle of the view gives
+    /// the starting bounds back, to within rounding as long as `precision_exhausted` stays false. Off-center, the
+    /// second zoom centers on a different point and the two don't cancel out.
+    pub fn zoom(&mut self, coords: (f32, f32), factor: f64) {
+        let x_range = self.x_range();
+        let y_range = self.y_range();
+
+        let mut new_x_range = x_range * float(factor);
+        if let Some(snapped) = self.zoom_snap.snap(to_f64(self.initial_x_range / new_x_range), factor < 1.0) {
+            new_x_range = self.initial_x_range / float(snapped);
+        }
+        // Scale both axes by the same amount so snapping doesn't change aspect ratio
+        let new_y_range = y_range * (new_x_range / x_range);
+
+        let (new_midpoint_x, new_midpoint_y) = self.pixel_to_complex_exact(coords);
+
+        let two = float::<F>(2.0);
+        self.x_scale_min = new_midpoint_x - (new_x_range / two);
+        self.x_scale_max = new_midpoint_x + (new_x_range / two);
+
+        self.y_scale_min = new_midpoint_y - (new_y_range / two);
+        self.y_scale_max = new_midpoint_y + (new_y_range / two);
+
+        self.apply_auto_iterations();
+        self.recalculate();
+    }
+
+    pub fn auto_iterations(&self) -> bool {
+        self.auto_iterations
+    }
+
+    /// Lets the iteration cap follow zoom depth, starting from the current cap at the current depth.
+    pub fn set_auto_iterations(&mut self, enabled: bool) {
+        self.auto_iterations = enabled;
+        if enabled {
+            let depth = self.magnification().ln().max(0.0);
+            self.auto_iterations_base = self.max_iterations - MandelbrotGenerator::AUTO_ITERATIONS_PER_DEPTH * depth;
+        }
+    }
+
+    fn apply_auto_iterations(&mut self) {
+        if !self.auto_iterations {
+            return;
+        }
+
+        // Depth in e-folds of magnification relative to the initial view
+        let depth = self.magnification().ln().max(0.0);
+        let max_iterations = (self.auto_iterations_base + MandelbrotGenerator::AUTO_ITERATIONS_PER_DEPTH * depth)
+            .round()
+            .min(MandelbrotGenerator::MAX_AUTO_ITERATIONS);
+
+        if max_iterations != self.max_iterations {
+            self.set_max_iterations(max_iterations);
+        }
+    }
+
+    pub fn max_iterations(&self) -> f64 {
+        self.max_iterations
+    }
+
+    pub fn set_max_iterations(&mut self, max_iterations: f64) {
+        self.max_iterations = max_iterations.max(MandelbrotGenerator::MIN_MAX_ITERATIONS);
+        self.escape_states = vec![None; self.width * self.height];
+        self.recalculate();
+    }
+
+    /// Maps a pixel position to the complex point under it.
+    pub fn pixel_to_complex(&self, coords: (f32, f32)) -> (f64, f64) {
+        let (x, y) = self.pixel_to_complex_exact(coords);
+        (to_f64(x), to_f64(y))
+    }
+
+    /// Maps a complex point to the pixel position over it, the inverse of `pixel_to_complex`. Points outside the view
+    /// land outside the frame.
+    pub fn complex_to_pixel(&self, point: (f64, f64)) -> (f64, f64) {
+        let (x_min, x_max) = (to_f64(self.x_scale_min), to_f64(self.x_scale_max));
+        let (y_min, y_max) = (to_f64(self.y_scale_min), to_f64(self.y_scale_max));
+        (
+            normalize(point.0, x_min, x_max, 0.0, self.width as f64),
+            normalize(point.1, y_min, y_max, 0.0, self.height as f64),
+        )
+    }
+
+    // Same as `pixel_to_complex`, in the generator's own precision.
+    fn pixel_to_complex_exact(&self, coords: (f32, f32)) -> (F, F) {
+        (
+            normalize(float(coords.0 as f64), F::zero(), float(self.width as f64), self.x_scale_min, self.x_scale_max),
+            normalize(float(coords.1 as f64), F::zero(), float(self.height as f64), self.y_scale_min, self.y_scale_max),
+        )
+    }
+
+    pub fn fractal_type(&self) -> FractalType {
+        self.fractal_type
+    }
+
+    pub fn set_fractal_type(&mut self, fractal_type: FractalType) {
+        self.fractal_type = fractal_type;
+        self.recalculate();
+    }
+
+    pub fn formula(&self) -> Formula {
+        self.formula
+    }
+
+    pub fn set_formula(&mut self, formula: Formula) {
+        self.formula = formula;
+        self.recalculate();
+    }
+
+    pub fn morph(&self) -> Option<(Formula, f64)> {
+        self.morph
+    }
+
+    /// Iterates a recurrence partway between another formula and the current one, each step the blend of both steps
+    /// by `progress`, from 0 for the other formula to 1 for the current one. The in-between frames turn one fractal
+    /// smoothly into the other. None goes back to the current formula alone.
+    pub fn set_morph(&mut self, morph: Option<(Formula, f64)>) {
+        self.morph = morph.map(|(from, progress)| (from, progress.clamp(0.0, 1.0)));
+        self.recalculate();
+    }
+
+    pub fn power(&self) -> u32 {
+        self.power
+    }
+
+    pub fn set_power(&mut self, power: u32) {
+        self.power = power.clamp(MandelbrotGenerator::MIN_POWER, MandelbrotGenerator::MAX_POWER);
+        self.recalculate();
+    }
+
+    // The cardioid and period-2 bulb tests only describe the standard Mandelbrot set.
+    fn uses_cardioid_check(&self) -> bool {
+        self.use_cardioid_check
+            && self.fractal_type == FractalType::Mandelbrot
+            && self.formula == Formula::Mandelbrot
+            && self.morph.is_none()
+            && self.power == 2
+    }
+
+    pub fn julia_c(&self) -> (f64, f64) {
+        self.julia_c
+    }
+
+    pub fn set_julia_c(&mut self, c: (f64, f64)) {
+        self.julia_c = c;
+        if self.fractal_type == FractalType::Julia {
+            self.recalculate();
+        }
+    }
+
+    pub fn stripe_frequency(&self) -> Option<f64> {
+        self.stripe_frequency
+    }
+
+    /// Sets how many stripes the stripe average wraps around each point, None to stop gathering it. Only integer
+    /// frequencies keep the stripes continuous across the negative real axis, where the argument jumps.
+    pub fn set_stripe_frequency(&mut self, frequency: Option<f64>) {
+        if frequency != self.stripe_frequency {
+            self.stripe_frequency = frequency;
+            self.escape_states = vec![None; self.width * self.height];
+            self.recalculate();
+        }
+    }
+
+    /// Translates the view by a distance in pixels, keeping the magnification unchanged. The view moves by whole
+    /// pixels and carries the sub-pixel remainder over to the next pan. A finished frame is shifted along and only the
+    /// strips scrolled into view get computed, anything else falls back to a recompute.
+    pub fn pan(&mut self, dx: f64, dy: f64) {
+        let dx = dx + self.pan_remainder.0;
+        let dy = dy + self.pan_remainder.1;
+        let (shift_x, shift_y) = (dx.round(), dy.round());
+        self.pan_remainder = (dx - shift_x, dy - shift_y);
+        if shift_x == 0.0 && shift_y == 0.0 {
+            return;
+        }
+
+        // One pixel step as `test_pixel` maps them, so shifted pixels land exactly where they would be recomputed
+        let x_shift = float::<F>(shift_x) * self.x_range() / float((self.width.max(2) - 1) as f64);
+        let y_shift = float::<F>(shift_y) * self.y_range() / float((self.height.max(2) - 1) as f64);
+
+        self.x_scale_min = self.x_scale_min - x_shift;
+        self.x_scale_max = self.x_scale_max - x_shift;
+
+        self.y_scale_min = self.y_scale_min - y_shift;
+        self.y_scale_max = self.y_scale_max - y_shift;
+
+        let (shift_x, shift_y) = (shift_x as isize, shift_y as isize);
+        if self.is_complete() && shift_x.unsigned_abs() < self.width && shift_y.unsigned_abs() < self.height {
+            self.shift_pixels(shift_x, shift_y);
+        } else {
+            self.recalculate();
+        }
+    }
+
+    // Moves the computed pixels by a whole pixel offset and computes the ones that have no source in the old frame.
+    fn shift_pixels(&mut self, shift_x: isize, shift_y: isize) {
+        let (width, height) = (self.width as isize, self.height as isize);
+        let source = |x: usize, y: usize| {
+            let (sx, sy) = (x as isize - shift_x, y as isize - shift_y);
+            (sx >= 0 && sx < width && sy >= 0 && sy < height).then(|| (sy * width + sx) as usize)
+        };
+
+        let old_counts = std::mem::take(&mut self.iteration_counts);
+        let old_angles = std::mem::take(&mut self.escape_angles);
+        let old_distances = std::mem::take(&mut self.distances);
+        let old_stripes = std::mem::take(&mut self.stripes);
+        let old_normal_angles = std::mem::take(&mut self.normal_angles);
+        let old_components = std::mem::take(&mut self.components);
+        self.iteration_counts = vec![0.0; self.width * self.height];
+        self.escape_angles = vec![0.0; self.width * self.height];
+        self.distances = vec![0.0; self.width * self.height];
+        self.stripes = vec![0.0; self.width * self.height];
+        self.normal_angles = vec![0.0; self.width * self.height];
+        self.components = vec![Component::Exterior; self.width * self.height];
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let value = match source(x, y) {
+                    Some(index) => PixelValue {
+                        iterations: old_counts[index],
+                        escape_angle: old_angles[index],
+                        distance: old_distances[index],
+                        stripe: old_stripes[index],
+                        normal_angle: old_normal_angles[index],
+                        component: old_components[index],
+                    },
+                    None if self.aa_samples > 1 => self.supersample_pixel(x, y),
+                    None => self.test_pixel(x as f64, y as f64, self.max_iterations, None).0,
+                };
+                self.store(x, y, value);
+            }
+        }
+
+        self.build_histogram();
+    }
+
+    /// Zooms to the region between two opposite pixel corners, given in any order.
+    pub fn zoom_to_rect(&mut self, top_left: (f32, f32), bottom_right: (f32, f32)) {
+        let (left, right) = (top_left.0.min(bottom_right.0), top_left.0.max(bottom_right.0));
+        let (top, bottom) = (top_left.1.min(bottom_right.1), top_left.1.max(bottom_right.1));
+
+        let (x_min, y_min) = self.pixel_to_complex_exact((left, top));
+        let (x_max, y_max) = self.pixel_to_complex_exact((right, bottom));
+
+        self.x_scale_min = x_min;
+        self.x_scale_max = x_max;
+
+        self.y_scale_min = y_min;
+        self.y_scale_max = y_max;
+        self.enforce_aspect();
+
+        self.apply_auto_iterations();
+        self.recalculate();
+    }
+
+    pub fn recalculate(&mut self) {
+        self.revision += 1;
+        self.tiles_done = 0;
+        self.recalculate = true;
+        self.refine = false;
+        self.histogram.clear();
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    // Takes over the pixels of a copy made by `at_resolution` at this generator's size once something else has
+    // computed it. Only meaningful if nothing recalculated since the copy was made, see `revision`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn adopt_frame(&mut self, frame: MandelbrotGenerator<F>) {
+        self.iteration_counts = frame.iteration_counts;
+        self.escape_angles = frame.escape_angles;
+        self.distances = frame.distances;
+        self.stripes = frame.stripes;
+        self.normal_angles = frame.normal_angles;
+        self.components = frame.components;
+        self.histogram = frame.histogram;
+        self.escape_states = vec![None; self.width * self.height];
+        self.current_x = 0;
+        self.current_y = 0;
+        self.recalculate = false;
+        self.refine = false;
+        self.tiles_done = 0;
+    }
+
+    /// Writes the smooth escape count of every pixel, for analysis or coloring in other tools. The format is the
+    /// bytes `VOM1`, the width and height as little-endian u32s, the iteration cap as a little-endian f64 and then
+    /// one little-endian f32 per pixel in row-major order. [`IterationGrid::read`] reads it back. Whatever pass is
+    /// current gets written, check `is_complete` first for the finished frame.
+    pub fn export_iterations<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let size = |value: usize| {
+            u32::try_from(value).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "render too large to export"))
+        };
+        writer.write_all(&IterationGrid::MAGIC)?;
+        writer.write_all(&size(self.width)?.to_le_bytes())?;
+        writer.write_all(&size(self.height)?.to_le_bytes())?;
+        writer.write_all(&self.max_iterations.to_le_bytes())?;
+        for count in &self.iteration_counts {
+            writer.write_all(&count.to_le_bytes())?;
+        }
+        writer.flush()
+    }
+
+    /// True once every pixel has been computed up to the full iteration cap.
+    pub fn is_complete(&self) -> bool {
+        !self.recalculate && !self.refine
+    }
+
+    pub fn tile_count(&self) -> usize {
+        self.width.div_ceil(MandelbrotGenerator::TILE_SIZE) * self.height.div_ceil(MandelbrotGenerator::TILE_SIZE)
+    }
+
+    /// The pixel columns and rows a tile covers. Tiles along the right and bottom edges are cut off by the frame.
+    pub fn tile(&self, index: usize) -> (Range<usize>, Range<usize>) {
+        let size = MandelbrotGenerator::TILE_SIZE;
+        let columns = self.width.div_ceil(size);
+        let (x, y) = (index % columns * size, index / columns * size);
+        (x..(x + size).min(self.width), y..(y + size).min(self.height))
+    }
+
+    /// Fraction of the current sweep's tiles computed so far, 1 once the frame is complete. A recompute with a preview
+    /// pass goes through two sweeps, the preview and the refinement.
+    pub fn progress(&self) -> f64 {
+        if self.is_complete() {
+            1.0
+        } else {
+            self.tiles_done as f64 / self.tile_count().max(1) as f64
+        }
+    }
+
+    /// Computes up to `count` more tiles of the current sweep, across all cores with the `parallel` feature, and returns
+    /// the indices of the tiles it finished. The last tile finishes the sweep. Views that go through a whole-frame path,
+    /// perturbation or hybrid precision, are computed at once when their first tiles are asked for.
+    pub fn compute_tiles(&mut self, count: usize) -> Range<usize> {
+        if self.is_complete() {
+            return 0..0;
+        }
+
+        let whole_frame = self.uses_perturbation() || self.hybrid_precision;
+        let tiles = if self.tiles_done == 0 && self.recalculate && whole_frame {
+            if self.uses_perturbation() {
+                self.compute_perturbed();
+            } else {
+                self.compute_all();
+            }
+            0..self.tile_count()
+        } else {
+            let tiles = self.tiles_done..(self.tiles_done + count).min(self.tile_count());
+
+            #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+            let computed: Vec<_> = {
+                use rayon::prelude::*;
+                tiles.clone().into_par_iter().map(|tile| self.sweep_tile(tile)).collect()
+            };
+            #[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+            let computed: Vec<_> = tiles.clone().map(|tile| self.sweep_tile(tile)).collect();
+
+            for (index, value, state) in computed.into_iter().flatten() {
+                if let Some(value) = value {
+                    self.store(index % self.width, index / self.width, value);
+                }
+                self.escape_states[index] = state;
+            }
+            // Source rows are above their mirrors, so they're in this batch or an earlier one
+            for tile in tiles.clone() {
+                let (columns, rows) = self.tile(tile);
+                self.mirror_rows(columns, rows);
+            }
+
+            self.tiles_done = tiles.end;
+            if self.tiles_done == self.tile_count() {
+                self.finish_sweep();
+            }
+            tiles
+        };
+
+        if self.is_complete() && self.histogram.is_empty() {
+            self.build_histogram();
+        }
+        tiles
+    }
+
+    // Runs `sweep_pixel` over a tile without touching the buffers, so tiles can be computed side by side. Yields each
+    // pixel's index with its new value, if any, and the escape state it leaves behind.
+    fn sweep_tile(&self, tile: usize) -> Vec<(usize, Option<PixelValue>, Option<EscapeState<F>>)> {
+        let (columns, rows) = self.tile(tile);
+        let mut computed = Vec::with_capacity(columns.len() * rows.len());
+        for y in rows {
+            for x in columns.clone() {
+                let index = self.index(x, y);
+                if self.mirror_source(y).is_some() {
+                    computed.push((index, None, None));
+                    continue;
+                }
+                let mut state = self.escape_states[index];
+                let value = self.sweep_pixel(x, y, &mut state);
+                computed.push((index, value, state));
+            }
+        }
+        computed
+    }
+
+    /// Computes the whole frame in two phases: every pixel in fast f32, then only the pixels whose neighbors disagree
+    /// about their escape count get recomputed in f64. Smooth exterior regions are where f32 holds up, the boundary is
+    /// where its rounding shows.
+    pub fn compute_all(&mut self) {
+        let pixel_step = to_f64(self.x_range()) / self.width as f64;
+        let pixel_step = pixel_step.min(to_f64(self.y_range()) / self.height as f64);
+        let magnitude = to_f64(self.x_scale_min.abs().max(self.x_scale_max.abs())
+            .max(self.y_scale_min.abs()).max(self.y_scale_max.abs()));
+        // Once pixels are only a few f32 ulps apart the fast pass would be blocky everywhere. The fast pass also only
+        // implements the quadratic recurrences.
+        let f32_usable = pixel_step > f32::EPSILON as f64 * magnitude * 16.0 && self.power == 2 && self.aa_samples == 1;
+
+        for y in 0..self.height {
+            if self.mirror_source(y).is_some() {
+                continue;
+            }
+            for x in 0..self.width {
+                let value = if f32_usable {
+                    self.test_pixel_f32(x as u32, y as u32)
+                } else if self.aa_samples > 1 {
+                    self.supersample_pixel(x, y)
+                } else {
+                    self.test_pixel(x as f64, y as f64, self.max_iterations, None).0
+                };
+                self.store(x, y, value);
+                let index = self.index(x, y);
+                self.escape_states[index] = None;
+            }
+        }
+
+        if f32_usable {
+            for (x, y) in self.boundary_pixels() {
+                if self.mirror_source(y).is_none() {
+                    let (value, _) = self.test_pixel(x as f64, y as f64, self.max_iterations, None);
+                    self.store(x, y, value);
+                }
+            }
+        }
+        self.mirror_rows(0..self.width, 0..self.height);
+
+        self.recalculate = false;
+        self.refine = false;
+    }
+
+    // Whether the next recalculation goes through `compute_perturbed`. It only pays off for float types wider than f64
+    // and only implements the plain quadratic Mandelbrot recurrence.
+    fn uses_perturbation(&self) -> bool {
+        self.perturbation
+            && to_f64(F::epsilon()) < f64::EPSILON
+            && self.needs_full_precision()
+            && self.fractal_type == FractalType::Mandelbrot
+            && self.formula == Formula::Mandelbrot
+            && self.morph.is_none()
+            && self.power == 2
+            && self.aa_samples == 1
+    }
+
+    /// Computes the whole frame by perturbation. One orbit at the view center is computed in full precision and every
+    /// pixel iterates only its offset from it in f64. Pixels whose offset stops being accurate against that orbit are
+    /// retried against a new reference placed on one of them, and computed directly if `MAX_REFERENCE_ORBITS` doesn't
+    /// resolve them.
+    pub fn compute_perturbed(&mut self) {
+        let mut pending: Vec<(usize, usize)> = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .collect();
+        let (mut cx, mut cy) = self.pixel_to_complex_exact((self.width as f32 / 2.0, self.height as f32 / 2.0));
+
+        for _ in 0..MandelbrotGenerator::MAX_REFERENCE_ORBITS {
+            if pending.is_empty() {
+                break;
+            }
+
+            let reference = ReferenceOrbit::new(cx, cy, self.max_iterations, self.bailout);
+            let mut glitched = Vec::new();
+            for (x, y) in pending {
+                match self.test_pixel_perturbed(x as f64, y as f64, &reference) {
+                    Some(value) => self.store(x, y, value),
+                    None => glitched.push((x, y)),
+                }
+            }
+
+            pending = glitched;
+            if let Some(&(x, y)) = pending.get(pending.len() / 2) {
+                (cx, cy) = self.pixel_position(float(x as f64), float(y as f64), self.bounds());
+            }
+        }
+
+        for (x, y) in pending {
+            let (value, _) = self.test_pixel(x as f64, y as f64, self.max_iterations, None);
+            self.store(x, y, value);
+        }
+
+        self.escape_states = vec![None; self.width * self.height];
+        self.recalculate = false;
+        self.refine = false;
+    }
+
+    // Whether the frame is its own mirror image across the real axis. That takes a view centered on the axis and a
+    // recurrence that commutes with conjugation, so the point below the axis escapes like the one above it. Julia sets
+    // are only symmetric under rotation, and the Burning Ship's absolute values break it, also halfway through a morph.
+    // Supersampling offsets aren't symmetric within a pixel.
+    fn is_symmetric(&self) -> bool {
+        self.fractal_type == FractalType::Mandelbrot
+            && self.formula != Formula::BurningShip
+            && !matches!(self.morph, Some((Formula::BurningShip, _)))
+            && self.aa_samples == 1
+            && self.y_scale_min == -self.y_scale_max
+    }
+
+    // The row a row is copied from instead of computed in a symmetric frame. Rows past the middle mirror the ones
+    // before it, an odd height's center row is on the axis and computed.
+    fn mirror_source(&self, y: usize) -> Option<usize> {
+        let source = self.height - 1 - y;
+        (source < y && self.is_symmetric()).then_some(source)
+    }
+
+    // Fills the mirrored pixels in a rectangle from their sources.
+    fn mirror_rows(&mut self, columns: Range<usize>, rows: Range<usize>) {
+        for y in rows {
+            if let Some(source) = self.mirror_source(y) {
+                for x in columns.clone() {
+                    self.store(x, y, self.pixel(x, source).conjugate());
+                }
+            }
+        }
+    }
+
+    // Pixels whose integer escape count differs from a direct neighbor by more than one band, or that sit on an
+    // interior/exterior edge.
+    fn boundary_pixels(&self) -> Vec<(usize, usize)> {
+        let mut flagged = Vec::new();
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let count = self.iteration_counts[self.index(x, y)];
+                let interior = count >= self.max_iterations as f32;
+                let neighbors = [
+                    (x.wrapping_sub(1), y),
+                    (x + 1, y),
+                    (x, y.wrapping_sub(1)),
+                    (x, y + 1),
+                ];
+
+                let is_boundary = neighbors.iter()
+                    .filter(|&&(nx, ny)| nx < self.width && ny < self.height)
+                    .any(|&(nx, ny)| {
+                        let neighbor = self.iteration_counts[self.index(nx, ny)];
+                        let neighbor_interior = neighbor >= self.max_iterations as f32;
+                        neighbor_interior != interior || (neighbor.floor() - count.floor()).abs() > 1.0
+                    });
+
+                if is_boundary {
+                    flagged.push((x, y));
+                }
+            }
+        }
+
+        flagged
+    }
+
+    /// Computes every pixel of the pending sweep across all cores.
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    pub fn recalculate_all(&mut self) {
+        let remaining = self.tile_count() - self.tiles_done;
+        self.compute_tiles(remaining);
+    }
+
+    // Computes a pixel for the current sweep, or returns None if the sweep leaves it unchanged. A recalculation runs
+    // up to the preview cap when previewing, a refinement continues the orbits the preview left unfinished.
+    fn sweep_pixel(&self, x: usize, y: usize, state: &mut Option<EscapeState<F>>) -> Option<PixelValue> {
+        if self.recalculate && self.aa_samples > 1 {
+            *state = None;
+            Some(self.supersample_pixel(x, y))
+        } else if self.recalculate {
+            let cap = if self.previewing() { self.preview_iterations } else { self.max_iterations };
+            let (value, next) = self.test_pixel(x as f64, y as f64, cap, None);
+            *state = next;
+            Some(value)
+        } else if self.refine {
+            state.take()
+                .map(|resume| self.test_pixel(x as f64, y as f64, self.max_iterations, Some(resume)).0)
+        } else {
+            None
+        }
+    }
+
+    // Averages the smooth iteration counts of the sub-pixel samples. A pixel counts as interior when most of its
+    // samples are, otherwise the interior samples are left out of the average so they don't drag it towards the cap.
+    fn supersample_pixel(&self, x: usize, y: usize) -> PixelValue {
+        let offsets = MandelbrotGenerator::SUPERSAMPLE_OFFSETS
+            .iter()
+            .find(|offsets| offsets.len() == self.aa_samples)
+            .expect("aa_samples is always a supported sample count");
+
+        let mut interior = None;
+        let mut exterior = None;
+        let mut exterior_samples = 0;
+        let mut total = 0.0;
+        let mut total_distance = 0.0;
+        let mut total_stripe = 0.0;
+        for (dx, dy) in offsets.iter() {
+            let (value, _) = self.test_pixel(x as f64 + dx, y as f64 + dy, self.max_iterations, None);
+            if value.iterations >= self.max_iterations as f32 {
+                interior.get_or_insert(value);
+            } else {
+                exterior_samples += 1;
+                total += value.iterations as f64;
+                total_distance += value.distance;
+                total_stripe += value.stripe;
+                exterior.get_or_insert(value);
+            }
+        }
+
+        match exterior {
+            Some(value) if exterior_samples * 2 >= offsets.len() => PixelValue {
+                iterations: (total / exterior_samples as f64) as f32,
+                distance: total_distance / exterior_samples as f64,
+                stripe: total_stripe / exterior_samples as f64,
+                ..value
+            },
+            _ => interior.or(exterior).expect("every pixel has at least one sample"),
+        }
+    }
+
+    pub fn aa_samples(&self) -> usize {
+        self.aa_samples
+    }
+
+    /// Sets the number of samples per pixel, 1, 2 or 4. Computing a frame costs that many times as much.
+    pub fn set_aa_samples(&mut self, samples: usize) {
+        if MandelbrotGenerator::SUPERSAMPLE_OFFSETS.iter().any(|offsets| offsets.len() == samples) {
+            self.aa_samples = samples;
+            self.escape_states = vec![None; self.width * self.height];
+            self.recalculate();
+        }
+    }
+
+    pub fn bailout(&self) -> f64 {
+        self.bailout
+    }
+
+    /// Sets the squared escape radius, never below `MIN_BAILOUT`. Unfinished orbits were tested against the old radius
+    /// so they're dropped along with the frame.
+    pub fn set_bailout(&mut self, bailout: f64) {
+        if bailout.is_finite() {
+            self.bailout = bailout.max(MandelbrotGenerator::MIN_BAILOUT);
+            self.escape_states = vec![None; self.width * self.height];
+            self.recalculate();
+        }
+    }
+
+    fn finish_sweep(&mut self) {
+        // A preview sweep is followed by a refinement sweep over the pixels it left unfinished
+        self.refine = self.recalculate && self.previewing();
+        self.recalculate = false;
+        self.tiles_done = 0;
+    }
+
+    // Counts the escaped pixels at each integer iteration and accumulates them. Interior pixels stay out of it, so the
+    // palette is spread over the exterior alone.
+    fn build_histogram(&mut self) {
+        let max_iterations = self.max_iterations as usize;
+        let mut counts = vec![0u32; max_iterations + 1];
+        let mut escaped = 0u32;
+        for &iterations in &self.iteration_counts {
+            if iterations >= self.max_iterations as f32 {
+                continue;
+            }
+            counts[(iterations.max(0.0).floor() as usize).min(max_iterations)] += 1;
+            escaped += 1;
+        }
+
+        let mut total = 0u32;
+        self.histogram = counts
+            .iter()
+            .map(|&count| {
+                total += count;
+                total as f32 / escaped.max(1) as f32
+            })
+            .collect();
+    }
+
+    /// Position in [0, 1] of a smooth iteration count in the histogram, interpolating within its integer bin. Falls back
+    /// to the linear position while no histogram is available.
+    pub fn histogram_position(&self, iterations: f32) -> f32 {
+        if self.histogram.is_empty() {
+            return (iterations / self.max_iterations as f32).clamp(0.0, 1.0);
+        }
+
+        let bin = (iterations.max(0.0).floor() as usize).min(self.histogram.len() - 1);
+        let lower = if bin == 0 { 0.0 } else { self.histogram[bin - 1] };
+        lower + (self.histogram[bin] - lower) * iterations.max(0.0).fract()
+    }
+
+    fn index(&self, x: usize, y: usize) -> usize {
+        y * self.width + x
+    }
+
+    // The last computed value of a pixel.
+    pub(crate) fn pixel(&self, x: usize, y: usize) -> PixelValue {
+        let index = self.index(x, y);
+        PixelValue {
+            iterations: self.iteration_counts[index],
+            escape_angle: self.escape_angles[index],
+            distance: self.distances[index],
+            stripe: self.stripes[index],
+            normal_angle: self.normal_angles[index],
+            component: self.components[index],
+        }
+    }
+
+    fn store(&mut self, x: usize, y: usize, value: PixelValue) {
+        let index = self.index(x, y);
+        self.iteration_counts[index] = value.iterations;
+        self.escape_angles[index] = value.escape_angle;
+        self.distances[index] = value.distance;
+        self.stripes[index] = value.stripe;
+        self.normal_angles[index] = value.normal_angle;
+        self.components[index] = value.component;
+    }
+
+    fn previewing(&self) -> bool {
+        // Supersampled pixels aren't resumable, they go straight to the full cap
+        self.preview_iterations > 0.0 && self.preview_iterations < self.max_iterations && self.aa_samples == 1
+    }
+
+    /// Scores how much boundary detail surrounds a pixel, using the last computed iteration counts. Mixed
+    /// interior/exterior windows and windows with widely varying escape counts score highest, while solid interior or
+    /// empty sea scores zero.
+    pub fn score_window(&self, px: usize, py: usize, half_window: usize) -> f64 {
+        let x_start = px.saturating_sub(half_window);
+        let x_end = (px + half_window).min(self.width - 1);
+        let y_start = py.saturating_sub(half_window);
+        let y_end = (py + half_window).min(self.height - 1);
+
+        let mut interior = 0usize;
+        let mut exterior = 0usize;
+        let mut sum = 0.0;
+        let mut sum_sq = 0.0;
+
+        for y in y_start..=y_end {
+            for &count in &self.iteration_counts[self.index(x_start, y)..=self.index(x_end, y)] {
+                if count >= self.max_iterations as f32 {
+                    interior += 1;
+                } else {
+                    // Log scale so a few slow escapes don't dominate the variance
+                    let value = (count as f64 + 1.0).ln();
+                    exterior += 1;
+                    sum += value;
+                    sum_sq += value * value;
+                }
+            }
+        }
+
+        let total = (interior + exterior) as f64;
+        let interior_fraction = interior as f64 / total;
+        let mixed = interior_fraction * (1.0 - interior_fraction);
+
+        let variance = if exterior > 0 {
+            let mean = sum / exterior as f64;
+            (sum_sq / exterior as f64 - mean * mean).max(0.0)
+        } else {
+            0.0
+        };
+
+        mixed + variance
+    }
+
+    // Runs the escape algorithm for a pixel up to `cap` iterations, optionally continuing from a previous pass. Returns
+    // the smoothed iteration count along with the orbit state if the pixel was still bounded when it hit the cap.
+    fn test_pixel(
+        &self,
+        px: f64,
+        py: f64,
+        cap: f64,
+        resume: Option<EscapeState<F>>,
+    ) -> (PixelValue, Option<EscapeState<F>>) {
+        if self.needs_full_precision() {
+            let (x0, y0) = self.pixel_position(float(px), float(py), self.bounds());
+            return self.escape(x0, y0, cap, resume);
+        }
+
+        let (x0, y0) = self.pixel_position(px, py, self.bounds().cast());
+        let (value, state) = self.escape(x0, y0, cap, resume.map(EscapeState::cast));
+        (value, state.map(EscapeState::cast))
+    }
+
+    // Maps a pixel center to the complex plane within `bounds`, in the float type the escape loop is about to use.
+    fn pixel_position<T: Float>(&self, px: T, py: T, bounds: Bounds<T>) -> (T, T) {
+        let x0 = normalize(px, T::zero(), float((self.width - 1) as f64), bounds.x_min, bounds.x_max);
+        let y0 = normalize(py, T::zero(), float((self.height - 1) as f64), bounds.y_min, bounds.y_max);
+        (x0, y0)
+    }
+
+    // Whether the escape loop has to run in `F`. A type wider than f64 is only worth its cost once neighboring pixels
+    // are fewer than `EXTENDED_PRECISION_ULPS` f64 ulps apart, until then f64 gives the same picture.
+    fn needs_full_precision(&self) -> bool {
+        if to_f64(F::epsilon()) >= f64::EPSILON {
+            return true;
+        }
+
+        self.pixel_size() < self.coordinate_magnitude() * f64::EPSILON * MandelbrotGenerator::EXTENDED_PRECISION_ULPS
+    }
+
+    // The largest coordinate in the view, which decides how finely `F` can resolve points in it.
+    fn coordinate_magnitude(&self) -> f64 {
+        to_f64(self.x_scale_min.abs().max(self.x_scale_max.abs())
+            .max(self.y_scale_min.abs()).max(self.y_scale_max.abs()))
+    }
+
+    // The escape loop itself, in whichever float type `test_pixel` picked for the view.
+    fn escape<T: Float>(&self, x0: T, y0: T, cap: f64, resume: Option<EscapeState<T>>) -> (PixelValue, Option<EscapeState<T>>) {
+        // The Mandelbrot set varies c across the plane starting from z = 0, a Julia set fixes c and varies z instead
+        let (cx, cy) = match self.fractal_type {
+            FractalType::Mandelbrot => (x0, y0),
+            FractalType::Julia => (float(self.julia_c.0), float(self.julia_c.1)),
+        };
+
+        let start = match (resume, self.fractal_type) {
+            (Some(state), _) => state,
+            (None, FractalType::Julia) => EscapeState {
+                x: x0,
+                y: y0,
+                dx: T::one(),
+                dy: T::zero(),
+                iteration: 0.0,
+                stripe_sum: 0.0,
+                stripe_last: 0.0,
+            },
+            (None, FractalType::Mandelbrot) => {
+                // Cardioid checking
+                if self.uses_cardioid_check() {
+                    if let Some(component) = Component::classify(to_f64(x0), to_f64(y0)) {
+                        return (PixelValue::interior(self.max_iterations, component), None);
+                    }
+                }
+
+                EscapeState {
+                    x: T::zero(),
+                    y: T::zero(),
+                    dx: T::zero(),
+                    dy: T::zero(),
+                    iteration: 0.0,
+                    stripe_sum: 0.0,
+                    stripe_last: 0.0,
+                }
+            }
+        };
+
+        let mut x = start.x;
+        let mut y = start.y;
+        let mut x2 = x * x;
+        let mut y2 = y * y;
+
+        // dz' = d z^(d-1) dz, plus one for the Mandelbrot set where c varies with the pixel. The non-analytic formulas
+        // use the same recurrence as an approximation
+        let mut dx = start.dx;
+        let mut dy = start.dy;
+        let dc = match self.fractal_type {
+            FractalType::Mandelbrot => T::one(),
+            FractalType::Julia => T::zero(),
+        };
+
+        let mut iteration = start.iteration;
+        let mut stripe_sum = start.stripe_sum;
+        let mut stripe_last = start.stripe_last;
+
+        // Compared against the orbit's own starting point, not the origin, which a Julia orbit or a resumed one may
+        // pass close to without being periodic
+        let mut x_old = start.x;
+        let mut y_old = start.y;
+        let mut period = 0;
+
+        let bailout = float::<T>(self.bailout);
+        let power = float::<T>(self.power as f64);
+        let epsilon = float::<T>(self.periodicity_epsilon * self.pixel_size());
+
+        // Escape algorithm
+        while ((x2 + y2) <= bailout) && iteration < cap {
+            let (zx, zy) = if self.power == 2 { (x, y) } else { complex_pow(x, y, self.power - 1) };
+            (dx, dy) = (
+                power * (zx * dx - zy * dy) + dc,
+                power * (zx * dy + zy * dx),
+            );
+
+            (x, y) = self.step(x, y, x2, y2, cx, cy);
+            x2 = x * x;
+            y2 = y * y;
+
+            iteration += 1.0;
+
+            if let Some(frequency) = self.stripe_frequency {
+                stripe_last = stripe_term(frequency, to_f64(x), to_f64(y));
+                stripe_sum += stripe_last;
+            }
+
+            // Periodicity checking, an orbit back within epsilon of an earlier point has settled into a cycle
+            if (x - x_old).abs() <= epsilon && (y - y_old).abs() <= epsilon {
+                return (PixelValue::interior(self.max_iterations, Component::OtherInterior), None);
+            }
+
+            period += 1;
+            if period > self.periodicity_interval {
+                period = 0;
+                x_old = x;
+                y_old = y;
+            }
+        }
+
+        // Interior points report exactly the cap, unsmoothed. Escapes always land below it: they happen after at most
+        // cap - 1 iterations and nu is positive once |z| > 2, which any bailout guarantees
+        if iteration >= cap {
+            // Still bounded, either truly interior or pending a deeper pass
+            let state = (cap < self.max_iterations).then_some(EscapeState {
+                x,
+                y,
+                dx,
+                dy,
+                iteration,
+                stripe_sum,
+                stripe_last,
+            });
+            return (PixelValue::interior(self.max_iterations, Component::OtherInterior), state);
+        }
+
+        // Renormalized count, nu = log_d(log2(|z|)). ln(|z|) is half of ln(|z|^2), no square root needed. Each step
+        // past the bailout raises |z| to roughly the d-th power and nu grows by one, so the count is continuous once
+        // the radius is large enough for c not to matter
+        let z2 = to_f64(x2 + y2);
+        let log_zn = z2.ln() / 2.0;
+        let nu = (log_zn / std::f64::consts::LN_2).ln() / (self.power as f64).ln();
+
+        let value = PixelValue {
+            iterations: (iteration + 1.0 - nu) as f32,
+            escape_angle: to_f64(y.atan2(x)),
+            distance: distance_estimate(z2, to_f64(dx * dx + dy * dy)),
+            stripe: stripe_average(stripe_sum, stripe_last, iteration, nu),
+            normal_angle: normal_angle(to_f64(x), to_f64(y), to_f64(dx), to_f64(dy)),
+            component: Component::Exterior,
+        };
+
+        (value, None)
+    }
+
+    // Runs the escape algorithm for a pixel as an offset from the reference orbit, up to the full cap. With z = Z + e
+    // and c = C + d for the reference's Z and C, the recurrence becomes e' = 2Ze + e^2 + d, all of it small enough for
+    // f64. Returns None if the pixel can't be trusted against this reference.
+    fn test_pixel_perturbed(&self, px: f64, py: f64, reference: &ReferenceOrbit<F>) -> Option<PixelValue> {
+        let (x0, y0) = self.pixel_position(float(px), float(py), self.bounds());
+
+        // Cardioid checking
+        if self.uses_cardioid_check() {
+            if let Some(component) = Component::classify(to_f64(x0), to_f64(y0)) {
+                return Some(PixelValue::interior(self.max_iterations, component));
+            }
+        }
+
+        let dcx = to_f64(x0 - reference.cx);
+        let dcy = to_f64(y0 - reference.cy);
+        let (mut ex, mut ey) = (0.0, 0.0);
+        let (mut dx, mut dy) = (0.0, 0.0);
+        let mut iteration = 0.0;
+        let (mut stripe_sum, mut stripe_last) = (0.0, 0.0);
+
+        for &(zx, zy) in &reference.points {
+            let (x, y) = (zx + ex, zy + ey);
+            let z2 = x * x + y * y;
+
+            // z_0 is the starting point, the terms begin with the first step like in `escape`
+            if let (Some(frequency), true) = (self.stripe_frequency, iteration > 0.0) {
+                stripe_last = stripe_term(frequency, x, y);
+                stripe_sum += stripe_last;
+            }
+
+            if iteration >= self.max_iterations {
+                return Some(PixelValue::interior(self.max_iterations, Component::OtherInterior));
+            }
+
+            if z2 > self.bailout {
+                // Same renormalization as `escape`, for power 2
+                let nu = (z2.ln() / 2.0 / std::f64::consts::LN_2).log2();
+                return Some(PixelValue {
+                    iterations: (iteration + 1.0 - nu) as f32,
+                    escape_angle: y.atan2(x),
+                    distance: distance_estimate(z2, dx * dx + dy * dy),
+                    stripe: stripe_average(stripe_sum, stripe_last, iteration, nu),
+                    normal_angle: normal_angle(x, y, dx, dy),
+                    component: Component::Exterior,
+                });
+            }
+
+            // Glitch detection, the offset has cancelled out most of the reference
+            if z2 < (zx * zx + zy * zy) * MandelbrotGenerator::GLITCH_TOLERANCE {
+                return None;
+            }
+
+            (dx, dy) = (2.0 * (x * dx - y * dy) + 1.0, 2.0 * (x * dy + y * dx));
+            (ex, ey) = (
+                2.0 * (zx * ex - zy * ey) + ex * ex - ey * ey + dcx,
+                2.0 * (zx * ey + zy * ex) + 2.0 * ex * ey + dcy,
+            );
+            iteration += 1.0;
+        }
+
+        // The reference escaped before this pixel did
+        None
+    }
+
+    // One application of the recurrence to z = x + iy, given its squared components.
+    #[inline(always)]
+    pub(crate) fn step<T: Float>(&self, x: T, y: T, x2: T, y2: T, cx: T, cy: T) -> (T, T) {
+        match self.morph {
+            None => self.step_formula(self.formula, x, y, x2, y2, cx, cy),
+            Some((from, progress)) => {
+                let (from_x, from_y) = self.step_formula(from, x, y, x2, y2, cx, cy);
+                let (to_x, to_y) = self.step_formula(self.formula, x, y, x2, y2, cx, cy);
+                let (keep, progress) = (float::<T>(1.0 - progress), float::<T>(progress));
+                (from_x * keep + to_x * progress, from_y * keep + to_y * progress)
+            }
+        }
+    }
+
+    #[inline(always)]
+    #[allow(clippy::too_many_arguments)]
+    fn step_formula<T: Float>(&self, formula: Formula, x: T, y: T, x2: T, y2: T, cx: T, cy: T) -> (T, T) {
+        if self.power == 2 {
+            let two = float::<T>(2.0);
+            let y = match formula {
+                Formula::Mandelbrot => two * x * y + cy,
+                // Conjugating z flips the sign of the cross term
+                Formula::Tricorn => -two * x * y + cy,
+                // Taking |x| and |y| before squaring only affects the cross term
+                Formula::BurningShip => two * (x * y).abs() + cy,
+            };
+            (x2 - y2 + cx, y)
+        } else {
+            let (zx, zy) = match formula {
+                Formula::Mandelbrot => (x, y),
+                Formula::Tricorn => (x, -y),
+                Formula::BurningShip => (x.abs(), y.abs()),
+            };
+            let (px, py) = complex_pow(zx, zy, self.power);
+            (px + cx, py + cy)
+        }
+    }
+
+    /// The orbit of the point under a pixel: every z the recurrence visits from the starting point until it escapes or
+    /// reaches the iteration cap, the escaped point included.
+    pub fn orbit(&self, coords: (f32, f32)) -> Vec<(f64, f64)> {
+        let (x0, y0) = self.pixel_to_complex_exact(coords);
+        let (cx, cy, mut x, mut y) = match self.fractal_type {
+            FractalType::Mandelbrot => (x0, y0, F::zero(), F::zero()),
+            FractalType::Julia => (float(self.julia_c.0), float(self.julia_c.1), x0, y0),
+        };
+
+        let bailout = float::<F>(self.bailout);
+        let mut orbit = vec![(to_f64(x), to_f64(y))];
+        while x * x + y * y <= bailout && (orbit.len() as f64) <= self.max_iterations {
+            (x, y) = self.step(x, y, x * x, y * y, cx, cy);
+            orbit.push((to_f64(x), to_f64(y)));
+        }
+
+        orbit
+    }
+
+    // Single precision version of the escape algorithm used for the fast phase of `compute_all`.
+    fn test_pixel_f32(&self, px: u32, py: u32) -> PixelValue {
+        let x0 = to_f64(normalize(
+            float(px as f64),
+            F::zero(),
+            float((self.width - 1) as f64),
+            self.x_scale_min,
+            self.x_scale_max,
+        ));
+
+        let y0 = to_f64(normalize(
+            float(py as f64),
+            F::zero(),
+            float((self.height - 1) as f64),
+            self.y_scale_min,
+            self.y_scale_max,
+        ));
+
+        let (cx, cy) = match self.fractal_type {
+            FractalType::Mandelbrot => {
+                // Cardioid checking
+                if self.uses_cardioid_check() {
+                    if let Some(component) = Component::classify(x0, y0) {
+                        return PixelValue::interior(self.max_iterations, component);
+                    }
+                }
+                (x0 as f32, y0 as f32)
+            }
+            FractalType::Julia => (self.julia_c.0 as f32, self.julia_c.1 as f32),
+        };
+
+        let (mut x, mut y): (f32, f32) = match self.fractal_type {
+            FractalType::Mandelbrot => (0.0, 0.0),
+            FractalType::Julia => (x0 as f32, y0 as f32),
+        };
+        let max_iterations = self.max_iterations as f32;
+        let bailout = self.bailout as f32;
+
+        let mut x2: f32 = x * x;
+        let mut y2: f32 = y * y;
+
+        let (mut dx, mut dy): (f32, f32) = match self.fractal_type {
+            FractalType::Mandelbrot => (0.0, 0.0),
+            FractalType::Julia => (1.0, 0.0),
+        };
+        let dc: f32 = match self.fractal_type {
+            FractalType::Mandelbrot => 1.0,
+            FractalType::Julia => 0.0,
+        };
+
+        let mut iteration: f32 = 0.0;
+        let (mut stripe_sum, mut stripe_last) = (0.0, 0.0);
+
+        // Escape algorithm
+        while ((x2 + y2) <= bailout) && iteration < max_iterations {
+            (dx, dy) = (2.0 * (x * dx - y * dy) + dc, 2.0 * (x * dy + y * dx));
+            (x, y) = self.step(x, y, x2, y2, cx, cy);
+            x2 = x * x;
+            y2 = y * y;
+
+            iteration += 1.0;
+
+            if let Some(frequency) = self.stripe_frequency {
+                stripe_last = stripe_term(frequency, x as f64, y as f64);
+                stripe_sum += stripe_last;
+            }
+        }
+
+        if iteration >= max_iterations {
+            return PixelValue::interior(self.max_iterations, Component::OtherInterior);
+        }
+
+        // Only used for power 2, so nu = log2(log2(|z|))
+        let log_zn = (x2 as f64 + y2 as f64).ln() / 2.0;
+        let nu = (log_zn / std::f64::consts::LN_2).log2();
+
+        PixelValue {
+            iterations: (iteration as f64 + 1.0 - nu) as f32,
+            escape_angle: (y as f64).atan2(x as f64),
+            distance: distance_estimate(x2 as f64 + y2 as f64, (dx * dx + dy * dy) as f64),
+            stripe: stripe_average(stripe_sum, stripe_last, iteration as f64, nu),
+            normal_angle: normal_angle(x as f64, y as f64, dx as f64, dy as f64),
+            component: Component::Exterior,
+        }
+    }
+}
+
+impl<F: Float + Send + Sync> Iterator for MandelbrotGenerator<F> {
+    type Item = PixelValue;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let x = self.current_x;
+        let y = self.current_y;
+
+        // Whole-frame paths compute the sweep up front, the rest of it just hands out the results
+        let sweep_start = x == 0 && y == 0;
+        if sweep_start && self.recalculate {
+            if self.uses_perturbation() {
+                self.compute_perturbed();
+            } else if self.hybrid_precision {
+                self.compute_all();
+            }
+        }
+
+        #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+        if sweep_start && !self.is_complete() {
+            self.recalculate_all();
+            self.bulk_sweep = true;
+        }
+
+        if !self.bulk_sweep {
+            let index = self.index(x, y);
+            let mirror = if self.is_complete() { None } else { self.mirror_source(y) };
+            if let Some(source) = mirror {
+                // The source row was swept already
+                self.store(x, y, self.pixel(x, source).conjugate());
+                self.escape_states[index] = None;
+            } else {
+                let mut state = self.escape_states[index];
+                if let Some(value) = self.sweep_pixel(x, y, &mut state) {
+                    self.store(x, y, value);
+                }
+                self.escape_states[index] = state;
+            }
+        }
+
+        self.current_x += 1;
+
+        if self.current_x >= self.width {
+            self.current_x = 0;
+            self.current_y += 1;
+        }
+
+        if self.current_y >= self.height {
+            self.current_y = 0;
+            self.current_x = 0;
+            if self.bulk_sweep {
+                self.bulk_sweep = false;
+            } else {
+                self.finish_sweep();
+            }
+
+            if self.is_complete() && self.histogram.is_empty() {
+                self.build_histogram();
+            }
+        }
+
+        Some(self.pixel(x, y))
+    }
+}
+
+// A palette control point as stored in palette files, a position along the gradient and its linear RGB color.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct PaletteStop {
+    position: f32,
+    red: f32,
+    green: f32,
+    blue: f32,
+}
+
+/// Everything that decides how a view looks, from the region and formula to the palette and coloring, for saving it to
+/// a file and coming back to it exactly. Taken with `MandelbrotRenderer::to_session` and restored with
+/// `from_session` or `restore_session`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    view: ViewParams<DoubleDouble>,
+    fractal_type: FractalType,
+    formula: Formula,
+    power: u32,
+    julia_c: (f64, f64),
+    bailout: f64,
+    aa_samples: usize,
+    palette: Vec<PaletteStop>,
+    palette_seed: Option<u64>,
+    gradient_space: GradientSpace,
+    palette_inverted: bool,
+    palette_period: f64,
+    palette_scale: PaletteScale,
+    palette_offset: f32,
+    coloring_mode: ColoringMode,
+}
+
+/// How an escaped pixel is mapped onto the palette.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ColoringMode {
+    /// Smooth iteration count mapped straight onto the palette.
+    Linear,
+    /// Decomposition by the argument of the escape point, revealing the external rays. In binary mode the bands are
+    /// split in two by the sign of the final imaginary part, otherwise the angle sweeps each band across its colors.
+    EscapeAngle { binary: bool },
+    /// Palette position by the fraction of escaped pixels with a lower count, so every color covers a similar area
+    /// however deep the view is.
+    Histogram,
+    /// Palette color darkened towards the set by the exterior distance estimate in pixels, keeping thin filaments
+    /// visible at any zoom.
+    DistanceEstimate,
+    /// Palette position by the average of sin(frequency arg z) along the orbit, which lays woven bands of stripes
+    /// around the set. Gathering it costs the generator a recompute whenever the frequency changes.
+    StripeAverage { frequency: f64 },
+    /// Palette color shaded as if the potential around the set were a lit surface, with the light coming from
+    /// `light_angle` degrees counterclockwise from the positive real axis. Changing the light only recolors.
+    NormalMap { light_angle: f64 },
+}
+
+impl ColoringMode {
+    // What the generator has to gather along each orbit for this mode.
+    fn stripe_frequency(self) -> Option<f64> {
+        match self {
+            ColoringMode::StripeAverage { frequency } => Some(frequency),
+            _ => None,
+        }
+    }
+}
+
+/// Curated palettes, each a gradient of control points so it stretches over any iteration cap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaletteKind {
+    Rainbow,
+    Grayscale,
+    Fire,
+    Ocean,
+    /// The default gradient of Ultra Fractal.
+    Classic,
+}
+
+impl PaletteKind {
+    pub const ALL: [PaletteKind; 5] = [
+        PaletteKind::Rainbow,
+        PaletteKind::Grayscale,
+        PaletteKind::Fire,
+        PaletteKind::Ocean,
+        PaletteKind::Classic,
+    ];
+
+    pub fn control_points(self) -> Vec<(f32, LinSrgb)> {
+        // Control points are picked as sRGB bytes, the palette itself is in linear light
+        let srgb = |red: u8, green: u8, blue: u8| Srgb::new(red, green, blue).into_format::<f32>().into_linear();
+
+        match self {
+            PaletteKind::Rainbow => MandelbrotRenderer::rainbow_control_points(),
+            PaletteKind::Grayscale => vec![
+                (0.0, srgb(0, 0, 0)),
+                (1.0, srgb(255, 255, 255)),
+            ],
+            PaletteKind::Fire => vec![
+                (0.0, srgb(0, 0, 0)),
+                (0.25, srgb(128, 0, 0)),
+                (0.5, srgb(255, 80, 0)),
+                (0.75, srgb(255, 200, 0)),
+                (1.0, srgb(255, 255, 224)),
+            ],
+            PaletteKind::Ocean => vec![
+                (0.0, srgb(0, 8, 32)),
+                (0.3, srgb(0, 48, 128)),
+                (0.6, srgb(0, 150, 170)),
+                (0.85, srgb(120, 220, 210)),
+                (1.0, srgb(240, 255, 255)),
+            ],
+            PaletteKind::Classic => vec![
+                (0.0, srgb(0, 7, 100)),
+                (0.16, srgb(32, 107, 203)),
+                (0.42, srgb(237, 255, 255)),
+                (0.6425, srgb(255, 170, 0)),
+                (0.8575, srgb(0, 2, 0)),
+                (1.0, srgb(0, 7, 100)),
+            ],
+        }
+    }
+}
+
+/// Color space the palette's control points are blended in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GradientSpace {
+    /// Straight blends of linear light, which can pass through muddy grays between saturated colors.
+    LinearRgb,
+    /// Perceptually uniform, blends keep their saturation and change lightness evenly.
+    Oklab,
+    /// CIELAB, perceptual like Oklab but bends blends through blue towards purple.
+    Lab,
+}
+
+impl GradientSpace {
+    pub fn next(self) -> Self {
+        match self {
+            GradientSpace::LinearRgb => GradientSpace::Oklab,
+            GradientSpace::Oklab => GradientSpace::Lab,
+            GradientSpace::Lab => GradientSpace::LinearRgb,
+        }
+    }
+}
+
+/// How escape counts are stretched before they're mapped onto the palette.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PaletteScale {
+    Linear,
+    /// Spreads the common low counts over more colors and squeezes the rare high ones together.
+    Log,
+    /// Between the two.
+    Sqrt,
+}
+
+impl PaletteScale {
+    pub fn next(self) -> Self {
+        match self {
+            PaletteScale::Linear => PaletteScale::Log,
+            PaletteScale::Log => PaletteScale::Sqrt,
+            PaletteScale::Sqrt => PaletteScale::Linear,
+        }
+    }
+
+    // Stretches an iteration count. Every scale maps 0 and `period` to themselves, so one pass through the palette
+    // still ends at the same count and only the colors in between move.
+    fn apply(self, iterations: f64, period: f64) -> f64 {
+        match self {
+            PaletteScale::Linear => iterations,
+            PaletteScale::Log => period * iterations.ln_1p() / period.ln_1p(),
+            PaletteScale::Sqrt => (iterations * period).sqrt(),
+        }
+    }
+}
+
+/// Colors a generator's output into an RGBA frame and owns everything about navigating it: palette, zoom animation,
+/// view history and the progressive preview.
+pub struct MandelbrotRenderer {
+    /// Stored in double-double so zooms can go past f64, the escape loop only switches to it once the view needs it.
+    pub generator: MandelbrotGenerator<DoubleDouble>,
+    width: usize,
+    height: usize,
+    palette_points: Vec<(f32, LinSrgb)>,
+    gradient_space: GradientSpace,
+    // Run through the expanded palette back to front, kept across palette changes until toggled again.
+    palette_inverted: bool,
+    palette: Vec<LinSrgb>,
+    // `palette` baked into sRGB bytes at `PALETTE_LUT_SIZE` evenly spaced phases over one period.
+    palette_lut: Vec<[u8; 4]>,
+    // Seed of the random palette shown, None for any other palette.
+    palette_seed: Option<u64>,
+    // Iterations covered by one pass through the palette, colors repeat beyond it.
+    palette_period: f64,
+    palette_scale: PaletteScale,
+    // Phase shift of the palette as a fraction of one period.
+    palette_offset: f32,
+    // Shift `palette_offset` by `palette_cycle_speed` periods per second, just recoloring the finished frame.
+    cycle_palette: bool,
+    pub palette_cycle_speed: f32,
+    last_cycle: Instant,
+    // Shown in place of `palette` while it runs, see `fade_to_random_palette`.
+    palette_fade: Option<PaletteFade>,
+    pub coloring_mode: ColoringMode,
+    /// Interpolate zooms over `ZOOM_ANIMATION_DURATION` instead of jumping straight to the new view, and morph from
+    /// one formula into the next over `FORMULA_MORPH_DURATION` when cycling them.
+    pub animate_zoom: bool,
+    // Range scale of a zoom in, zooming out uses its reciprocal. Kept within `MIN_ZOOM_FACTOR..=MAX_ZOOM_FACTOR`.
+    zoom_factor: f64,
+    animation: Option<AnimationState>,
+    formula_morph: Option<FormulaMorph>,
+    history: ViewHistory,
+    // Debug overlay coloring interior pixels by the hyperbolic component they belong to.
+    show_components: bool,
+    // Ordered dithering when quantizing colors to 8 bits, see `dithered_rgba`.
+    dither: bool,
+    // Shows the Buddhabrot, or the Nebulabrot when `nebulabrot` is set, instead of the escape-time frame while set.
+    // See `render_buddhabrot` and `render_nebulabrot`.
+    buddhabrot: Option<Buddhabrot>,
+    nebulabrot: bool,
+    /// Iteration caps of the red, green and blue layers of the Nebulabrot.
+    pub nebulabrot_caps: [usize; 3],
+    redraw: bool,
+    /// Show coarse passes while a recompute is pending, see `PROGRESSIVE_START_LEVEL`.
+    pub progressive: bool,
+    /// Stop computing and keep showing the last drawn frame. Navigation still updates the view, the work it causes
+    /// just waits until computing resumes.
+    pub paused: bool,
+    // Computes frames off the UI thread while set, see `set_background_computing`.
+    #[cfg(not(target_arch = "wasm32"))]
+    worker: Option<RenderWorker>,
+    // Accumulates the Buddhabrot off the UI thread while it's shown, see `draw_density_from_worker`.
+    #[cfg(not(target_arch = "wasm32"))]
+    density_worker: Option<DensityWorker>,
+    // Block size of the next coarse pass, 1 once only the full resolution frame is left.
+    progressive_level: usize,
+    // Time spent drawing the sweep in progress, and the total of the last sweep that ran to completion.
+    sweep_time: Duration,
+    last_render_time: Option<Duration>,
+    // Factor the generator's resolution is above the window's, see `set_render_scale`.
+    render_scale: u32,
+    // Average the render scale's pixels in linear light rather than as sRGB bytes, see `toggle_linear_averaging`.
+    linear_averaging: bool,
+    // The colored frame at the generator's resolution while the render scale is above 1, box filtered into the frame
+    // buffer. Empty otherwise, pixels are colored straight into the frame buffer.
+    scaled_buffer: Vec<u8>,
+    frame_buffer: Vec<u8>,
+}
+
+impl MandelbrotRenderer {
+    pub const TITLE: &'static str = "Visions of Mandelbrot";
+    pub const ZOOM_ANIMATION_DURATION: Duration = Duration::from_millis(200);
+    /// How long `cycle_formula` takes to morph one formula into the next while `animate_zoom` is on.
+    pub const FORMULA_MORPH_DURATION: Duration = Duration::from_secs(2);
+    /// How long `fade_to_random_palette` takes to blend from the old palette into the new one.
+    pub const PALETTE_FADE_DURATION: Duration = Duration::from_secs(2);
+    pub const DEFAULT_ZOOM_FACTOR: f64 = 0.5;
+    /// Far enough from 1.0 that a zoom always does something, close enough to 0 to dive quickly.
+    pub const MIN_ZOOM_FACTOR: f64 = 0.01;
+    pub const MAX_ZOOM_FACTOR: f64 = 0.95;
+    /// Exponent applied to the factor per step, above one zooms more aggressively.
+    pub const ZOOM_FACTOR_STEP: f64 = 1.5;
+    /// Colors the control points are expanded to. The palette is indexed cyclically so this is independent of the
+    /// iteration cap.
+    pub const PALETTE_SIZE: usize = 256;
+    /// Entries in the RGBA lookup table the palette is baked into whenever it changes, so the plain coloring modes
+    /// don't interpolate and encode a color per pixel. Lookups blend the two entries around a phase and stay within a
+    /// unit of the interpolated color, except right where a channel climbs off zero and across the wrap from the last
+    /// palette entry to the first, which can be a jump from white to black.
+    pub const PALETTE_LUT_SIZE: usize = 4096;
+    /// Matches the default iteration cap, so by default the palette is spread over the whole range once.
+    pub const DEFAULT_PALETTE_PERIOD: f64 = MandelbrotGenerator::DEFAULT_MAX_ITERATIONS;
+    pub const MIN_PALETTE_PERIOD: f64 = 4.0;
+    pub const DEFAULT_PALETTE_CYCLE_SPEED: f32 = 0.05;
+    /// Fraction of a palette period one manual shift of the palette offset moves the colors by.
+    pub const PALETTE_OFFSET_STEP: f32 = 1.0 / 32.0;
+    /// Distance in pixels from the set at which distance estimate coloring reaches full brightness.
+    pub const DISTANCE_ESTIMATE_FALLOFF: f64 = 4.0;
+    /// Stripes the stripe average coloring wraps around each point, whole numbers from 1 to the maximum.
+    pub const DEFAULT_STRIPE_FREQUENCY: f64 = 5.0;
+    pub const MAX_STRIPE_FREQUENCY: f64 = 32.0;
+    pub const DEFAULT_LIGHT_ANGLE: f64 = 45.0;
+    /// Height of the light above the plane for normal map shading, relative to the surface normal's horizontal part.
+    /// Higher lights flatten the shading, the darkest side facing away from it gets (h - 1) / (h + 1) of the color.
+    pub const LIGHT_HEIGHT: f64 = 1.5;
+    /// Thresholds of the ordered dither, in sixteenths. Neighboring pixels round a color up at different fractions
+    /// of a step so smooth gradients break up into a fine pattern instead of bands.
+    const BAYER_MATRIX: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+    /// Block size of the first coarse pass in progressive mode, halved every frame until it reaches one pixel.
+    pub const PROGRESSIVE_START_LEVEL: usize = 8;
+    /// Time spent computing tiles per drawn frame before the finished ones are shown, about one frame at 60 Hz.
+    pub const TILE_FRAME_BUDGET: Duration = Duration::from_millis(16);
+    /// Render scales cycled through, each computes scale² pixels for every one shown.
+    pub const RENDER_SCALES: [u32; 3] = [1, 2, 3];
+    /// Squared escape radii cycled through, radius 2, 16 and 256.
+    pub const BAILOUT_PRESETS: [f64; 3] = [4.0, 256.0, 65536.0];
+    /// Random samples added to the Buddhabrot per batch on its worker thread, or per drawn frame on the web where
+    /// there is none, small enough to keep the window responsive there.
+    pub const BUDDHABROT_SAMPLES_PER_FRAME: usize = 100_000;
+    /// Samples after which the Buddhabrot stops accumulating until the view changes.
+    pub const BUDDHABROT_MAX_SAMPLES: u64 = 100_000_000;
+    /// The usual choice, long-lived orbits in red down to short ones in blue.
+    pub const DEFAULT_NEBULABROT_CAPS: [usize; 3] = [5000, 500, 50];
+
+    pub fn new(width: usize, height: usize, generator: MandelbrotGenerator<DoubleDouble>) -> Self {
+        let palette = MandelbrotRenderer::builtin_palette(PaletteKind::Rainbow, MandelbrotRenderer::PALETTE_SIZE);
+        MandelbrotRenderer {
+            width,
+            height,
+            palette_points: PaletteKind::Rainbow.control_points(),
+            gradient_space: GradientSpace::LinearRgb,
+            palette_inverted: false,
+            palette_lut: MandelbrotRenderer::bake_palette(&palette),
+            palette,
+            palette_seed: None,
+            palette_period: MandelbrotRenderer::DEFAULT_PALETTE_PERIOD,
+            palette_scale: PaletteScale::Linear,
+            palette_offset: 0.0,
+            cycle_palette: false,
+            palette_cycle_speed: MandelbrotRenderer::DEFAULT_PALETTE_CYCLE_SPEED,
+            last_cycle: Instant::now(),
+            palette_fade: None,
+            coloring_mode: ColoringMode::Linear,
+            animate_zoom: false,
+            zoom_factor: MandelbrotRenderer::DEFAULT_ZOOM_FACTOR,
+            animation: None,
+            formula_morph: None,
+            history: ViewHistory::new(),
+            show_components: false,
+            dither: false,
+            buddhabrot: None,
+            nebulabrot: false,
+            nebulabrot_caps: MandelbrotRenderer::DEFAULT_NEBULABROT_CAPS,
+            redraw: true,
+            progressive: false,
+            paused: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            worker: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            density_worker: None,
+            progressive_level: 1,
+            sweep_time: Duration::ZERO,
+            last_render_time: None,
+            render_scale: 1,
+            linear_averaging: false,
+            scaled_buffer: Vec::new(),
+            frame_buffer: vec![0xffu8; width * height * 4],
+            generator,
+        }
+    }
+
+    /// Advances any animation, computes whatever the current pass needs and copies the frame into `frame`, which has
+    /// to be `width * height` RGBA pixels.
+    pub fn draw(&mut self, frame: &mut [u8]) {
+        // The coloring mode can be set directly, or outlive the generator it was set on
+        self.generator.set_stripe_frequency(self.coloring_mode.stripe_frequency());
+        self.advance_animation();
+        self.advance_formula_morph();
+        self.advance_palette_cycle();
+        self.advance_palette_fade();
+
+        if self.buddhabrot.is_some() {
+            #[cfg(not(target_arch = "wasm32"))]
+            self.draw_density_from_worker();
+            #[cfg(target_arch = "wasm32")]
+            if self.buddhabrot_pending() && !self.paused {
+                self.render_density(MandelbrotRenderer::BUDDHABROT_SAMPLES_PER_FRAME);
+            }
+            frame.copy_from_slice(&self.frame_buffer);
+            return;
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.worker.is_some() {
+            self.draw_from_worker();
+            frame.copy_from_slice(&self.frame_buffer);
+            return;
+        }
+
+        if self.redraw && !self.paused {
+            // Only frames that compute something count towards the render time, recoloring a finished frame doesn't
+            let computing = !self.generator.is_complete();
+            let started = Instant::now();
+
+            // A fresh recompute starts the schedule over, coarse passes leave the generator itself untouched
+            if self.progressive && self.generator.recalculate && self.progressive_level == 1 {
+                self.progressive_level = MandelbrotRenderer::PROGRESSIVE_START_LEVEL;
+            }
+
+            if self.progressive && self.progressive_level > 1 {
+                self.draw_coarse(self.progressive_level);
+                self.progressive_level /= 2;
+                self.sweep_time += started.elapsed();
+                frame.copy_from_slice(&self.frame_buffer);
+                return;
+            }
+
+            if computing {
+                self.draw_tiles(started);
+                self.sweep_time += started.elapsed();
+            } else {
+                self.draw_to_frame_buffer();
+            }
+
+            // Keep drawing until the generator has refined past its preview pass
+            self.redraw = !self.generator.is_complete();
+            if computing && !self.redraw {
+                self.last_render_time = Some(std::mem::take(&mut self.sweep_time));
+
+                // The histogram only exists once the sweep is done, so color the finished frame again with it
+                if self.coloring_mode == ColoringMode::Histogram {
+                    self.draw_to_frame_buffer();
+                }
+            }
+        }
+
+        frame.copy_from_slice(&self.frame_buffer);
+    }
+
+    // Hands a pending recompute to the worker and shows whatever frames it has finished. The frame for the current
+    // view is adopted by the generator, after which recoloring works on it as usual.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn draw_from_worker(&mut self) {
+        let Some(worker) = self.worker.as_mut() else {
+            return;
+        };
+
+        let revision = self.generator.revision();
+        let (width, height) = self.generator.dimensions();
+        if !self.generator.is_complete() && !self.paused && worker.requested_revision() != Some(revision) {
+            worker.request(self.generator.at_resolution(width, height));
+        }
+
+        if let Some(mut frame) = worker.latest() {
+            if frame.revision() == revision && !self.generator.is_complete() {
+                self.last_render_time = worker.elapsed();
+                self.generator.adopt_frame(frame);
+                self.redraw = true;
+            } else if frame.dimensions() == (width, height) {
+                // An older view, still better than the frame before it while panning or zooming
+                let buffer = self.render_generator(&mut frame);
+                self.present(buffer);
+            }
+        }
+
+        if self.redraw && self.generator.is_complete() {
+            self.draw_to_frame_buffer();
+            self.redraw = false;
+        }
+    }
+
+    // Keeps a batch of Buddhabrot samples in the works on the density worker and shows each one as it comes back. A
+    // density that went stale on the way, because the view or caps changed meanwhile, is dropped for a fresh one.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn draw_density_from_worker(&mut self) {
+        let caps = self.density_caps();
+        let worker = self.density_worker.get_or_insert_with(|| {
+            DensityWorker::spawn(MandelbrotRenderer::BUDDHABROT_SAMPLES_PER_FRAME)
+        });
+
+        if let Some((buddhabrot, frame)) = worker.latest() {
+            if buddhabrot.matches(&self.generator, &caps) {
+                self.buddhabrot = Some(buddhabrot);
+                self.present(frame);
+            }
+        }
+        if !self.buddhabrot.as_ref().is_some_and(|buddhabrot| buddhabrot.matches(&self.generator, &caps)) {
+            self.buddhabrot = Some(Buddhabrot::new(&self.generator, &caps));
+        }
+
+        let pending = self.buddhabrot_pending() && !self.paused;
+        if let (Some(worker), Some(buddhabrot)) = (self.density_worker.as_mut(), &self.buddhabrot) {
+            if pending && !worker.is_busy() {
+                worker.request(buddhabrot.clone());
+            }
+        }
+    }
+
+    // Computes tiles until the frame budget runs out, coloring each batch into the frame buffer as it finishes, so a
+    // slow frame fills in piece by piece instead of holding up the window.
+    fn draw_tiles(&mut self, started: Instant) {
+        #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+        let batch = rayon::current_num_threads();
+        #[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+        let batch = 1;
+
+        let (width, _) = self.generator.dimensions();
+        while !self.generator.is_complete() && started.elapsed() < MandelbrotRenderer::TILE_FRAME_BUDGET {
+            for tile in self.generator.compute_tiles(batch) {
+                let (columns, rows) = self.generator.tile(tile);
+                for y in rows.clone() {
+                    for x in columns.clone() {
+                        let offset = (y * width + x) * 4;
+                        let color = self.color_pixel(&self.generator, self.generator.pixel(x, y), (x, y));
+                        self.render_target()[offset..offset + 4].copy_from_slice(&color);
+                    }
+                }
+                self.downscale(columns, rows);
+            }
+        }
+    }
+
+    fn draw_to_frame_buffer(&mut self) {
+        let mut buffer = std::mem::take(self.render_target());
+        let (width, _) = self.generator.dimensions();
+        for (index, pixel) in buffer.chunks_exact_mut(4).enumerate() {
+            let value = self.generator.next().unwrap();
+            pixel.copy_from_slice(&self.color_pixel(&self.generator, value, (index % width, index / width)));
+        }
+        self.present(buffer);
+    }
+
+    // The buffer pixels at the generator's resolution are colored into.
+    fn render_target(&mut self) -> &mut Vec<u8> {
+        if self.render_scale > 1 {
+            &mut self.scaled_buffer
+        } else {
+            &mut self.frame_buffer
+        }
+    }
+
+    // Takes a whole colored frame at the generator's resolution and shows it.
+    fn present(&mut self, buffer: Vec<u8>) {
+        *self.render_target() = buffer;
+        let (width, height) = self.generator.dimensions();
+        self.downscale(0..width, 0..height);
+    }
+
+    // Averages each block of render_scale² pixels of the scaled buffer into a frame buffer pixel, for the blocks
+    // overlapping a rectangle of the generator's pixels.
+    fn downscale(&mut self, columns: Range<usize>, rows: Range<usize>) {
+        let scale = self.render_scale as usize;
+        if scale == 1 {
+            return;
+        }
+
+        let scaled_width = self.width * scale;
+        let samples = (scale * scale) as u32;
+        // Decoded once per call, the bytes only take 256 values
+        let linear: Option<[f32; 256]> = self.linear_averaging.then(|| {
+            std::array::from_fn(|value| Srgb::new(value as u8, 0, 0).into_format::<f32>().into_linear().red)
+        });
+        for y in rows.start / scale..rows.end.div_ceil(scale) {
+            for x in columns.start / scale..columns.end.div_ceil(scale) {
+                let mut sums = [0u32; 4];
+                let mut light = [0.0f32; 3];
+                for sy in y * scale..(y + 1) * scale {
+                    let offset = (sy * scaled_width + x * scale) * 4;
+                    for sample in self.scaled_buffer[offset..offset + scale * 4].chunks_exact(4) {
+                        match &linear {
+                            Some(linear) => {
+                                for (light, &channel) in light.iter_mut().zip(sample) {
+                                    *light += linear[channel as usize];
+                                }
+                            }
+                            None => {
+                                for (sum, &channel) in sums.iter_mut().zip(sample) {
+                                    *sum += channel as u32;
+                                }
+                            }
+                        }
+                    }
+                }
+
+                let offset = (y * self.width + x) * 4;
+                let target = &mut self.frame_buffer[offset..offset + 4];
+                if linear.is_some() {
+                    let [red, green, blue] = light.map(|light| light / samples as f32);
+                    target.copy_from_slice(&MandelbrotRenderer::color_to_rgba(&LinSrgb::new(red, green, blue)));
+                } else {
+                    for (target, sum) in target.iter_mut().zip(sums) {
+                        *target = ((sum + samples / 2) / samples) as u8;
+                    }
+                }
+            }
+        }
+    }
+
+    // Computes the view at 1/level of the resolution and scales it up into the frame buffer in level sized blocks.
+    fn draw_coarse(&mut self, level: usize) {
+        let coarse_width = self.width.div_ceil(level).max(1);
+        let coarse_height = self.height.div_ceil(level).max(1);
+        let coarse = self.render_generator(&mut self.generator.at_resolution(coarse_width, coarse_height));
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let source = ((y / level).min(coarse_height - 1) * coarse_width + (x / level).min(coarse_width - 1)) * 4;
+                let target = (y * self.width + x) * 4;
+                self.frame_buffer[target..target + 4].copy_from_slice(&coarse[source..source + 4]);
+            }
+        }
+    }
+
+    // Runs a generator through a complete frame and colors it into a new RGBA buffer.
+    fn render_generator(&self, generator: &mut MandelbrotGenerator<DoubleDouble>) -> Vec<u8> {
+        let mut buffer = vec![0xffu8; generator.width * generator.height * 4];
+        generator.set_stripe_frequency(self.coloring_mode.stripe_frequency());
+
+        // Compute everything before coloring, histogram coloring needs the finished frame
+        for _ in 0..generator.width * generator.height {
+            generator.next();
+        }
+        for (index, pixel) in buffer.chunks_exact_mut(4).enumerate() {
+            let value = generator.next().unwrap();
+            let position = (index % generator.width, index / generator.width);
+            pixel.copy_from_slice(&self.color_pixel(generator, value, position));
+        }
+
+        buffer
+    }
+
+    // `position` is the pixel's column and row, which the dither pattern is laid out by.
+    fn color_pixel(
+        &self,
+        generator: &MandelbrotGenerator<DoubleDouble>,
+        value: PixelValue,
+        position: (usize, usize),
+    ) -> [u8; 4] {
+        let mandelbrot_value = value.iterations;
+        if self.show_components && value.component != Component::Exterior {
+            MandelbrotRenderer::component_color(value.component)
+        } else if mandelbrot_value >= generator.max_iterations as f32 {
+            [0, 0, 0, 0xff]
+        } else {
+            let value_iterations = mandelbrot_value.max(0.0) as f64;
+            let mut color = match self.coloring_mode {
+                ColoringMode::Linear => return self.lut_rgba(self.palette_phase(value_iterations), position),
+                // The histogram already is a position along the palette, it's spread over one period
+                // and already evenly spread, so the palette scale is left out of it
+                ColoringMode::Histogram => {
+                    let phase = generator.histogram_position(mandelbrot_value) as f64 + self.palette_offset as f64;
+                    return self.lut_rgba(phase.fract(), position);
+                }
+                // Same for the stripe average, which is already between 0 and 1
+                ColoringMode::StripeAverage { .. } => {
+                    return self.lut_rgba((value.stripe + self.palette_offset as f64).fract(), position);
+                }
+                // Each band blends from its own color to the next one's across the escape angle
+                ColoringMode::EscapeAngle { binary: false } => {
+                    let band = value_iterations.floor();
+                    let fraction = ((value.escape_angle + std::f64::consts::PI) / std::f64::consts::TAU) as f32;
+                    Gradient::from([
+                        (0.0, self.palette_color(band)),
+                        (1.0, self.palette_color(band + 1.0))
+                    ]).get(fraction)
+                }
+                ColoringMode::DistanceEstimate => {
+                    let pixels = value.distance / generator.pixel_size();
+                    let brightness = (pixels / MandelbrotRenderer::DISTANCE_ESTIMATE_FALLOFF).clamp(0.0, 1.0).sqrt();
+                    self.palette_color(value_iterations) * brightness as f32
+                }
+                // Lambert shading, the normal's horizontal part is a unit vector and the light is lifted above it
+                ColoringMode::NormalMap { light_angle } => {
+                    let incidence = (value.normal_angle - light_angle.to_radians()).cos();
+                    let height = MandelbrotRenderer::LIGHT_HEIGHT;
+                    self.palette_color(value_iterations) * ((incidence + height) / (1.0 + height)) as f32
+                }
+                ColoringMode::EscapeAngle { binary: true } => self.palette_color(value_iterations),
+            };
+
+            if self.coloring_mode == (ColoringMode::EscapeAngle { binary: true }) && value.escape_angle < 0.0 {
+                color *= 0.35;
+            }
+
+            self.quantize(&color, position)
+        }
+    }
+
+    // The palette color at an iteration count, wrapping around every `palette_period` iterations and shifted by
+    // `palette_offset`.
+    fn palette_color(&self, iterations: f64) -> LinSrgb {
+        MandelbrotRenderer::palette_color_at(&self.palette, self.palette_phase(iterations))
+    }
+
+    // The palette color at a phase, looked up in `palette_lut` for the modes that use the color as is. The bytes are
+    // blended between the two entries around the phase, snapping to the nearer one falls behind by several units
+    // where the sRGB curve is steep near black. The table is already quantized, so dithering interpolates the color
+    // itself instead.
+    fn lut_rgba(&self, phase: f64, position: (usize, usize)) -> [u8; 4] {
+        if self.dither {
+            let color = MandelbrotRenderer::palette_color_at(&self.palette, phase);
+            return MandelbrotRenderer::dithered_rgba(&color, position);
+        }
+        let size = self.palette_lut.len();
+        let position = phase * size as f64;
+        let entry = position.floor() as usize % size;
+        let (from, to) = (self.palette_lut[entry], self.palette_lut[(entry + 1) % size]);
+        let t = position.fract();
+        std::array::from_fn(|channel| {
+            (from[channel] as f64 + (to[channel] as f64 - from[channel] as f64) * t).round() as u8
+        })
+    }
+
+    fn quantize(&self, color: &LinSrgb, position: (usize, usize)) -> [u8; 4] {
+        if self.dither {
+            MandelbrotRenderer::dithered_rgba(color, position)
+        } else {
+            MandelbrotRenderer::color_to_rgba(color)
+        }
+    }
+
+    // Position of an iteration count within the palette's period after `palette_scale`, in [0, 1).
+    fn palette_phase(&self, iterations: f64) -> f64 {
+        let scaled = self.palette_scale.apply(iterations, self.palette_period);
+        (scaled / self.palette_period + self.palette_offset as f64).fract()
+    }
+
+    // Interpolates between the two palette entries around a phase in [0, 1).
+    fn palette_color_at(palette: &[LinSrgb], phase: f64) -> LinSrgb {
+        let position = phase * palette.len() as f64;
+        let index = position.floor() as usize % palette.len();
+        let next = (index + 1) % palette.len();
+
+        Gradient::from([
+            (0.0, palette[index]),
+            (1.0, palette[next])
+        ]).get(position.fract() as f32)
+    }
+
+    fn bake_palette(palette: &[LinSrgb]) -> Vec<[u8; 4]> {
+        (0..MandelbrotRenderer::PALETTE_LUT_SIZE)
+            .map(|entry| {
+                let phase = entry as f64 / MandelbrotRenderer::PALETTE_LUT_SIZE as f64;
+                MandelbrotRenderer::color_to_rgba(&MandelbrotRenderer::palette_color_at(palette, phase))
+            })
+            .collect()
+    }
+
+    /// Renders the current view at an arbitrary resolution into a new RGBA buffer, leaving the live view untouched.
+    pub fn render_to_image(&self, width: usize, height: usize) -> Vec<u8> {
+        self.render_generator(&mut self.generator.at_resolution(width, height))
+    }
+
+    fn advance_animation(&mut self) {
+        if let Some(animation) = &self.animation {
+            let t = animation.started.elapsed().as_secs_f64() / MandelbrotRenderer::ZOOM_ANIMATION_DURATION.as_secs_f64();
+            if t >= 1.0 {
+                self.generator.apply_bounds(animation.target);
+                self.animation = None;
+            } else {
+                // Ease in-out cubic
+                let eased = if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                };
+                let bounds = animation.start.interpolate(&animation.target, eased);
+                self.generator.apply_bounds(bounds);
+            }
+            self.redraw = true;
+        }
+    }
+
+    // Moves the morph along only once the frame of its last step is finished, so every step is shown however long it
+    // takes to compute, and ends it on the plain formula.
+    fn advance_formula_morph(&mut self) {
+        let Some(morph) = &self.formula_morph else {
+            return;
+        };
+        if !self.generator.is_complete() {
+            return;
+        }
+
+        let t = morph.started.elapsed().as_secs_f64() / MandelbrotRenderer::FORMULA_MORPH_DURATION.as_secs_f64();
+        if t >= 1.0 {
+            self.generator.set_morph(None);
+            self.formula_morph = None;
+        } else {
+            self.generator.set_morph(Some((morph.from, t)));
+        }
+        self.redraw = true;
+    }
+
+    fn advance_palette_cycle(&mut self) {
+        let now = Instant::now();
+        if self.cycle_palette {
+            let elapsed = now.duration_since(self.last_cycle).as_secs_f32();
+            self.palette_offset = (self.palette_offset + elapsed * self.palette_cycle_speed).rem_euclid(1.0);
+            self.redraw = true;
+        }
+        self.last_cycle = now;
+    }
+
+    fn advance_palette_fade(&mut self) {
+        let Some(fade) = self.palette_fade.take() else {
+            return;
+        };
+
+        let t = fade.started.elapsed().as_secs_f32() / MandelbrotRenderer::PALETTE_FADE_DURATION.as_secs_f32();
+        if t >= 1.0 {
+            self.palette_lut = MandelbrotRenderer::bake_palette(&fade.to);
+            self.palette = fade.to;
+            self.redraw = true;
+        } else {
+            self.blend_palettes(&fade.from, &fade.to, t);
+            self.palette_fade = Some(fade);
+        }
+    }
+
+    pub fn is_cycling_palette(&self) -> bool {
+        self.cycle_palette
+    }
+
+    pub fn toggle_palette_cycling(&mut self) {
+        self.cycle_palette = !self.cycle_palette;
+        self.last_cycle = Instant::now();
+    }
+
+    /// True while there is still work to show, an animation, formula morph or palette fade running, a recompute that
+    /// hasn't been drawn yet or a Buddhabrot still accumulating.
+    pub fn is_busy(&self) -> bool {
+        let pending = if self.buddhabrot.is_some() { self.buddhabrot_pending() } else { self.redraw };
+        let animating = self.animation.is_some() || self.formula_morph.is_some() || self.palette_fade.is_some();
+        (pending && !self.paused) || animating
+    }
+
+    /// Fraction of the current pass computed so far, see `MandelbrotGenerator::progress`.
+    pub fn progress(&self) -> f64 {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(worker) = &self.worker {
+            if !self.generator.is_complete() {
+                return worker.progress();
+            }
+        }
+        self.generator.progress()
+    }
+
+    /// How long the last full recompute took to draw, including its preview and refine passes.
+    pub fn last_render_time(&self) -> Option<Duration> {
+        self.last_render_time
+    }
+
+    /// The last drawn frame as tightly packed RGBA rows.
+    pub fn rgba_buffer(&self) -> &[u8] {
+        &self.frame_buffer
+    }
+
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// A window title naming the current view center and magnification.
+    pub fn title(&self) -> String {
+        let (re, im) = self.generator.center();
+        format!(
+            "{} - {:+.10} {:+.10}i at {:.3e}x",
+            MandelbrotRenderer::TITLE,
+            re,
+            im,
+            self.generator.magnification()
+        )
+    }
+
+    /// The view center and magnification as three numbers separated by spaces, the center to full precision. Short
+    /// enough to share, and `set_center_and_zoom` jumps back to it.
+    pub fn location(&self) -> String {
+        let (re, im) = self.generator.precise_center();
+        format!("{} {} {:e}", re, im, self.generator.magnification())
+    }
+
+    // The view being navigated to, which is the end of the zoom animation while one runs.
+    fn current_view(&self) -> ViewParams<DoubleDouble> {
+        match &self.animation {
+            Some(animation) => ViewParams {
+                x_min: animation.target.x_min,
+                x_max: animation.target.x_max,
+                y_min: animation.target.y_min,
+                y_max: animation.target.y_max,
+                max_iterations: self.generator.max_iterations(),
+            },
+            None => self.generator.view(),
+        }
+    }
+
+    /// Remembers the current view for undo before navigating away from it. Pans are too fine grained to record one by
+    /// one, so a drag records once when it starts.
+    pub fn record_view(&mut self) {
+        let view = self.current_view();
+        self.history.record(view);
+    }
+
+    /// Steps back to the view before the last navigation, returns false when there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        let current = self.current_view();
+        match self.history.undo(current) {
+            Some(view) => {
+                self.set_view(view);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        let current = self.current_view();
+        match self.history.redo(current) {
+            Some(view) => {
+                self.set_view(view);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn zoom(&mut self, coords: (f32, f32), factor: f64) {
+        self.record_view();
+        let start = self.generator.bounds();
+        // A zoom in the middle of another one's animation carries on from where that one was heading
+        if let Some(animation) = &self.animation {
+            self.generator.apply_bounds(animation.target);
+        }
+        self.generator.zoom(self.render_coords(coords), factor);
+
+        if self.animate_zoom {
+            let target = self.generator.bounds();
+            self.generator.apply_bounds(start);
+            self.animation = Some(AnimationState {
+                start,
+                target,
+                started: Instant::now(),
+            });
+        }
+
+        self.redraw = true;
+    }
+
+    /// Zooms about a point straight away, without animating or recording the view. For continuous gestures like a
+    /// pinch, which record the view once when they start.
+    pub fn zoom_immediately(&mut self, coords: (f32, f32), factor: f64) {
+        self.animation = None;
+        self.generator.zoom(self.render_coords(coords), factor);
+        self.redraw = true;
+    }
+
+    pub fn zoom_factor(&self) -> f64 {
+        self.zoom_factor
+    }
+
+    /// Non-finite factors are ignored, anything else is clamped into range.
+    pub fn set_zoom_factor(&mut self, factor: f64) {
+        if factor.is_finite() {
+            self.zoom_factor = factor.clamp(MandelbrotRenderer::MIN_ZOOM_FACTOR, MandelbrotRenderer::MAX_ZOOM_FACTOR);
+        }
+    }
+
+    /// Makes zooms go deeper per click, or gentler.
+    pub fn step_zoom_factor(&mut self, more_aggressive: bool) {
+        let exponent = if more_aggressive {
+            MandelbrotRenderer::ZOOM_FACTOR_STEP
+        } else {
+            1.0 / MandelbrotRenderer::ZOOM_FACTOR_STEP
+        };
+        self.set_zoom_factor(self.zoom_factor.powf(exponent));
+    }
+
+    pub fn zoom_in(&mut self, coords: (f32, f32)) {
+        self.zoom(coords, self.zoom_factor);
+    }
+
+    pub fn zoom_out(&mut self, coords: (f32, f32)) {
+        self.zoom(coords, 1.0 / self.zoom_factor);
+    }
+
+    /// Recenters the view on a pixel without changing the scale. It's a pan, so the part of the frame that stays in
+    /// view is reused rather than recomputed.
+    pub fn center_on(&mut self, coords: (f32, f32)) {
+        self.record_view();
+        let (width, height) = (self.width as f32, self.height as f32);
+        self.pan(width / 2.0 - coords.0, height / 2.0 - coords.1);
+        self.redraw = true;
+    }
+
+    pub fn zoom_to_rect(&mut self, top_left: (f32, f32), bottom_right: (f32, f32)) {
+        // A zero-sized selection would collapse the view
+        if top_left.0 == bottom_right.0 || top_left.1 == bottom_right.1 {
+            return;
+        }
+
+        self.record_view();
+        self.generator.zoom_to_rect(self.render_coords(top_left), self.render_coords(bottom_right));
+        self.redraw = true;
+    }
+
+    pub fn toggle_julia(&mut self) {
+        let fractal_type = match self.generator.fractal_type() {
+            FractalType::Mandelbrot => FractalType::Julia,
+            FractalType::Julia => FractalType::Mandelbrot,
+        };
+        self.generator.set_fractal_type(fractal_type);
+        self.redraw = true;
+    }
+
+    /// Steps through 1, 2 and 4 samples per pixel.
+    pub fn cycle_aa_samples(&mut self) {
+        let samples = match self.generator.aa_samples() {
+            1 => 2,
+            2 => 4,
+            _ => 1,
+        };
+        self.generator.set_aa_samples(samples);
+        self.redraw = true;
+    }
+
+    /// Steps the squared escape radius through `BAILOUT_PRESETS`, falling back to the first for any other value.
+    pub fn cycle_bailout(&mut self) {
+        let presets = MandelbrotRenderer::BAILOUT_PRESETS;
+        let bailout = presets
+            .iter()
+            .position(|&bailout| bailout == self.generator.bailout())
+            .map_or(presets[0], |index| presets[(index + 1) % presets.len()]);
+        self.generator.set_bailout(bailout);
+        self.redraw = true;
+    }
+
+    /// Switches to the next formula, morphing into it over `FORMULA_MORPH_DURATION` while `animate_zoom` is on.
+    pub fn cycle_formula(&mut self) {
+        let from = self.generator.formula();
+        self.generator.set_formula(from.next());
+        self.formula_morph = self.animate_zoom.then(|| FormulaMorph { from, started: Instant::now() });
+        self.generator.set_morph(self.animate_zoom.then_some((from, 0.0)));
+        self.redraw = true;
+    }
+
+    pub fn step_power(&mut self, delta: i32) {
+        let power = (self.generator.power() as i32 + delta).max(0) as u32;
+        self.generator.set_power(power);
+        self.redraw = true;
+    }
+
+    /// Uses the complex point under the cursor as the Julia parameter.
+    pub fn set_julia_c_at(&mut self, coords: (f32, f32)) {
+        let c = self.generator.pixel_to_complex(self.render_coords(coords));
+        self.generator.set_julia_c(c);
+        self.redraw = true;
+    }
+
+    /// Moves the view so the content follows a mouse drag of `dx`, `dy` pixels.
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        let scale = self.render_scale as f64;
+        self.generator.pan(dx as f64 * scale, dy as f64 * scale);
+        self.redraw = true;
+    }
+
+    /// Moves the view by a fraction of its current extent, positive values look right and down.
+    pub fn pan_by_fraction(&mut self, fx: f64, fy: f64) {
+        self.record_view();
+        let (width, height) = self.generator.dimensions();
+        self.generator.pan(-fx * width as f64, -fy * height as f64);
+        self.redraw = true;
+    }
+
+    /// Changes the window size, ignored if either side is zero like `MandelbrotGenerator::resize`.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let scale = self.render_scale as usize;
+        self.width = width;
+        self.height = height;
+        self.frame_buffer = vec![0xffu8; width * height * 4];
+        self.scaled_buffer = if scale > 1 { vec![0xffu8; width * height * scale * scale * 4] } else { Vec::new() };
+        self.generator.resize(width * scale, height * scale);
+        self.redraw = true;
+    }
+
+    pub fn render_scale(&self) -> u32 {
+        self.render_scale
+    }
+
+    /// Computes the view at `scale` times the window's resolution in each direction and averages the pixels down,
+    /// smoothing edges at scale² times the cost. Unlike supersampling this keeps every computed pixel, so recoloring
+    /// and the progressive passes work on them as usual.
+    pub fn set_render_scale(&mut self, scale: u32) {
+        if scale >= 1 && scale != self.render_scale {
+            // Resizing the generator widens the view along with it, the window still shows the same region
+            let bounds = self.generator.bounds();
+            self.render_scale = scale;
+            self.resize(self.width, self.height);
+            self.generator.apply_bounds(bounds);
+        }
+    }
+
+    pub fn is_averaging_linear(&self) -> bool {
+        self.linear_averaging
+    }
+
+    /// Switches the render scale between averaging its pixels as sRGB bytes, the default, and in linear light. Byte
+    /// averages are perceptual, a pixel half covered by a thin white filament over black comes out mid-gray, which
+    /// keeps edges looking as sharp as at the window's resolution. Linear light is physically right, that pixel gets
+    /// half the light, so thin bright filaments stay brighter and thin dark ones fade faster.
+    pub fn toggle_linear_averaging(&mut self) {
+        self.linear_averaging = !self.linear_averaging;
+        let (width, height) = self.generator.dimensions();
+        self.downscale(0..width, 0..height);
+    }
+
+    /// Steps through `RENDER_SCALES`, falling back to the first for any other value.
+    pub fn cycle_render_scale(&mut self) {
+        let scales = MandelbrotRenderer::RENDER_SCALES;
+        let scale = scales
+            .iter()
+            .position(|&scale| scale == self.render_scale)
+            .map_or(scales[0], |index| scales[(index + 1) % scales.len()]);
+        self.set_render_scale(scale);
+    }
+
+    // Maps a window position to the generator's pixels.
+    fn render_coords(&self, coords: (f32, f32)) -> (f32, f32) {
+        let scale = self.render_scale as f32;
+        (coords.0 * scale, coords.1 * scale)
+    }
+
+    /// The orbit of the point under a window position, see `MandelbrotGenerator::orbit`.
+    pub fn orbit(&self, coords: (f32, f32)) -> Vec<(f64, f64)> {
+        self.generator.orbit(self.render_coords(coords))
+    }
+
+    /// Maps a complex point to the window position over it.
+    pub fn complex_to_pixel(&self, point: (f64, f64)) -> (f64, f64) {
+        let (x, y) = self.generator.complex_to_pixel(point);
+        let scale = self.render_scale as f64;
+        (x / scale, y / scale)
+    }
+
+    /// Recomputes the fractal after a generator setting changed.
+    pub fn recalculate(&mut self) {
+        self.generator.recalculate();
+        self.redraw = true;
+    }
+
+    /// Switches how pixels are colored. Stripe average coloring needs the generator to gather it, so switching to or
+    /// from it, or changing its frequency, recomputes the view.
+    pub fn set_coloring_mode(&mut self, mode: ColoringMode) {
+        self.coloring_mode = mode;
+        self.generator.set_stripe_frequency(mode.stripe_frequency());
+        self.redraw = true;
+    }
+
+    /// Jumps straight to a saved view, abandoning any zoom animation in progress.
+    pub fn set_view(&mut self, view: ViewParams<DoubleDouble>) {
+        self.animation = None;
+        self.generator.set_view(view);
+        self.redraw = true;
+    }
+
+    /// Jumps to a point at a magnification, see `MandelbrotGenerator::set_center_and_zoom`. The view it leaves goes
+    /// on the undo history.
+    pub fn set_center_and_zoom(&mut self, re: DoubleDouble, im: DoubleDouble, magnification: f64) {
+        self.record_view();
+        self.animation = None;
+        self.generator.set_center_and_zoom(re, im, magnification);
+        self.redraw = true;
+    }
+
+    /// Goes back to the starting view, see `MandelbrotGenerator::reset_view`. The view it leaves goes on the undo
+    /// history.
+    pub fn reset_view(&mut self) {
+        self.record_view();
+        self.animation = None;
+        self.generator.reset_view();
+        self.redraw = true;
+    }
+
+    pub fn set_max_iterations(&mut self, max_iterations: f64) {
+        self.generator.set_max_iterations(max_iterations);
+        self.redraw = true;
+    }
+
+    /// Snaps the iteration cap to the next power of two above, or below, the current one.
+    pub fn step_max_iterations_power_of_two(&mut self, up: bool) {
+        let current = self.generator.max_iterations() as u64;
+        let target = if up {
+            (current + 1).next_power_of_two()
+        } else {
+            current.next_power_of_two() / 2
+        };
+
+        self.set_max_iterations(target as f64);
+    }
+
+    pub fn palette_period(&self) -> f64 {
+        self.palette_period
+    }
+
+    /// Scales the number of iterations one pass through the palette covers. Only recolors, the fractal is reused.
+    pub fn scale_palette_period(&mut self, factor: f64) {
+        self.palette_period = (self.palette_period * factor).max(MandelbrotRenderer::MIN_PALETTE_PERIOD);
+        self.redraw = true;
+    }
+
+    pub fn palette_scale(&self) -> PaletteScale {
+        self.palette_scale
+    }
+
+    /// Changes how escape counts are stretched across the palette. Interior pixels stay black under any scale.
+    pub fn set_palette_scale(&mut self, scale: PaletteScale) {
+        self.palette_scale = scale;
+        self.redraw = true;
+    }
+
+    pub fn is_palette_inverted(&self) -> bool {
+        self.palette_inverted
+    }
+
+    /// Reverses the order of the palette's colors, whichever palette it is. Palettes switched to afterwards are
+    /// reversed too, until this is called again.
+    pub fn invert_palette(&mut self) {
+        self.palette_inverted = !self.palette_inverted;
+        self.rebuild_palette();
+        self.redraw = true;
+    }
+
+    pub fn palette_offset(&self) -> f32 {
+        self.palette_offset
+    }
+
+    /// Rotates where the palette starts by a fraction of its period, positive moves colors towards lower iteration
+    /// counts. Like a step of palette cycling it only recolors, the fractal is reused.
+    pub fn shift_palette_offset(&mut self, delta: f32) {
+        self.palette_offset = (self.palette_offset + delta).rem_euclid(1.0);
+        self.redraw = true;
+    }
+
+    /// Replaces the palette's control points and expands them to `PALETTE_SIZE` colors.
+    pub fn set_palette_points(&mut self, points: Vec<(f32, LinSrgb)>) {
+        self.palette_points = points;
+        self.palette_seed = None;
+        self.rebuild_palette();
+        self.redraw = true;
+    }
+
+    fn rebuild_palette(&mut self) {
+        // Whatever the palette changed to wins over a fade still running
+        self.palette_fade = None;
+        self.palette = MandelbrotRenderer::expand_palette_in(
+            &self.palette_points,
+            MandelbrotRenderer::PALETTE_SIZE,
+            self.gradient_space,
+        );
+        if self.palette_inverted {
+            self.palette.reverse();
+        }
+        self.palette_lut = MandelbrotRenderer::bake_palette(&self.palette);
+    }
+
+    /// The palette's control points as JSON, independent of the resolution it's currently expanded to.
+    pub fn export_palette(&self) -> String {
+        serde_json::to_string_pretty(&self.palette_stops()).expect("palette stops always serialize")
+    }
+
+    fn palette_stops(&self) -> Vec<PaletteStop> {
+        self.palette_points
+            .iter()
+            .map(|(position, color)| PaletteStop {
+                position: *position,
+                red: color.red,
+                green: color.green,
+                blue: color.blue,
+            })
+            .collect()
+    }
+
+    // Control points in order from stops in any order.
+    fn stops_to_points(stops: &[PaletteStop]) -> Vec<(f32, LinSrgb)> {
+        let mut points: Vec<(f32, LinSrgb)> = stops
+            .iter()
+            .map(|stop| (stop.position, LinSrgb::new(stop.red, stop.green, stop.blue)))
+            .collect();
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        points
+    }
+
+    /// Replaces the palette with control points exported by `export_palette`. The current palette is kept on error.
+    pub fn import_palette(&mut self, json: &str) -> Result<(), serde_json::Error> {
+        let stops: Vec<PaletteStop> = serde_json::from_str(json)?;
+        if stops.is_empty() {
+            return Err(serde::de::Error::custom("a palette needs at least one control point"));
+        }
+
+        self.set_palette_points(MandelbrotRenderer::stops_to_points(&stops));
+        Ok(())
+    }
+
+    /// Everything that decides how the current view looks, see `Session`.
+    pub fn to_session(&self) -> Session {
+        let generator = &self.generator;
+        Session {
+            view: generator.view(),
+            fractal_type: generator.fractal_type(),
+            formula: generator.formula(),
+            power: generator.power(),
+            julia_c: generator.julia_c(),
+            bailout: generator.bailout(),
+            aa_samples: generator.aa_samples(),
+            palette: self.palette_stops(),
+            palette_seed: self.palette_seed,
+            gradient_space: self.gradient_space,
+            palette_inverted: self.palette_inverted,
+            palette_period: self.palette_period,
+            palette_scale: self.palette_scale,
+            palette_offset: self.palette_offset,
+            coloring_mode: self.coloring_mode,
+        }
+    }
+
+    /// A renderer of the given size showing a saved session.
+    pub fn from_session(width: usize, height: usize, session: &Session) -> Self {
+        let generator = MandelbrotGenerator::new(width, height, session.view.max_iterations);
+        let mut renderer = MandelbrotRenderer::new(width, height, generator);
+        renderer.restore_session(session);
+        renderer
+    }
+
+    /// Goes back to a saved session and recomputes, keeping this renderer's size and navigation settings. The view it
+    /// leaves goes on the undo history. A session without any palette control points keeps the current palette.
+    pub fn restore_session(&mut self, session: &Session) {
+        self.record_view();
+        self.animation = None;
+
+        let generator = &mut self.generator;
+        generator.set_fractal_type(session.fractal_type);
+        generator.set_formula(session.formula);
+        generator.set_power(session.power);
+        generator.set_julia_c(session.julia_c);
+        generator.set_bailout(session.bailout);
+        generator.set_aa_samples(session.aa_samples);
+        generator.set_view(session.view);
+
+        if !session.palette.is_empty() {
+            self.palette_points = MandelbrotRenderer::stops_to_points(&session.palette);
+            self.palette_seed = session.palette_seed;
+        }
+        self.gradient_space = session.gradient_space;
+        self.palette_inverted = session.palette_inverted;
+        self.rebuild_palette();
+        self.palette_period = session.palette_period.max(MandelbrotRenderer::MIN_PALETTE_PERIOD);
+        self.palette_scale = session.palette_scale;
+        self.palette_offset = session.palette_offset.rem_euclid(1.0);
+        self.coloring_mode = session.coloring_mode;
+        self.redraw = true;
+    }
+
+    pub fn set_builtin_palette(&mut self, kind: PaletteKind) {
+        self.set_palette_points(kind.control_points());
+    }
+
+    /// Switches to a random palette from a fresh seed, see `palette_seed`.
+    pub fn randomize_palette(&mut self) {
+        self.set_random_palette(rand::random());
+    }
+
+    /// Switches to the random palette a seed produces, the same one every time.
+    pub fn set_random_palette(&mut self, seed: u64) {
+        self.set_palette_points(MandelbrotRenderer::random_control_points(seed));
+        self.palette_seed = Some(seed);
+    }
+
+    /// Like `randomize_palette`, but cross-fades from the current palette to the new one over `PALETTE_FADE_DURATION`
+    /// instead of switching at once. The new palette's seed and control points are current right away.
+    pub fn fade_to_random_palette(&mut self) {
+        let from = self.palette.clone();
+        self.randomize_palette();
+        let to = self.palette.clone();
+        self.blend_palettes(&from, &to, 0.0);
+        self.palette_fade = Some(PaletteFade {
+            from,
+            to,
+            started: Instant::now(),
+        });
+    }
+
+    /// Shows a linear blend of two expanded palettes, `a` at `t` = 0 and `b` at 1, rebaking the lookup table for it.
+    /// Palettes of different lengths are both resampled to the longer one. Empty palettes are ignored. The blend lasts
+    /// until the palette next changes, the control points, seed and exported palette are left as they were.
+    pub fn blend_palettes(&mut self, a: &[LinSrgb], b: &[LinSrgb], t: f32) {
+        if a.is_empty() || b.is_empty() {
+            return;
+        }
+
+        let t = t.clamp(0.0, 1.0);
+        let size = a.len().max(b.len());
+        self.palette = (0..size)
+            .map(|index| {
+                let phase = index as f64 / size as f64;
+                let from = MandelbrotRenderer::palette_color_at(a, phase);
+                let to = MandelbrotRenderer::palette_color_at(b, phase);
+                from + (to - from) * t
+            })
+            .collect();
+        self.palette_lut = MandelbrotRenderer::bake_palette(&self.palette);
+        self.redraw = true;
+    }
+
+    /// The seed that produced the current palette if it's a random one, for getting it back with `set_random_palette`.
+    pub fn palette_seed(&self) -> Option<u64> {
+        self.palette_seed
+    }
+
+    pub fn expand_palette(points: &[(f32, LinSrgb)], n_colors: usize) -> Vec<LinSrgb> {
+        Gradient::from(points.to_vec()).take(n_colors).collect()
+    }
+
+    /// Same as `expand_palette`, blending in the given color space. Perceptual blends between in-gamut colors can
+    /// still leave the sRGB gamut, those are clamped back into it.
+    pub fn expand_palette_in(points: &[(f32, LinSrgb)], n_colors: usize, space: GradientSpace) -> Vec<LinSrgb> {
+        match space {
+            GradientSpace::LinearRgb => MandelbrotRenderer::expand_palette(points, n_colors),
+            GradientSpace::Oklab => {
+                let points: Vec<(f32, Oklab)> = points.iter().map(|&(at, color)| (at, Oklab::from_color(color))).collect();
+                Gradient::from(points).take(n_colors).map(|color| LinSrgb::from_color(color).clamp()).collect()
+            }
+            GradientSpace::Lab => {
+                let points: Vec<(f32, Lab)> = points.iter().map(|&(at, color)| (at, Lab::from_color(color))).collect();
+                Gradient::from(points).take(n_colors).map(|color| LinSrgb::from_color(color).clamp()).collect()
+            }
+        }
+    }
+
+    pub fn gradient_space(&self) -> GradientSpace {
+        self.gradient_space
+    }
+
+    /// Re-expands the current control points blending in another color space.
+    pub fn set_gradient_space(&mut self, space: GradientSpace) {
+        self.gradient_space = space;
+        self.rebuild_palette();
+        self.redraw = true;
+    }
+
+    pub fn builtin_palette(kind: PaletteKind, n_colors: usize) -> Vec<LinSrgb> {
+        MandelbrotRenderer::expand_palette(&kind.control_points(), n_colors)
+    }
+
+    pub fn random_control_points(seed: u64) -> Vec<(f32, LinSrgb)> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        // One channel value for each of the three channels of the five control points
+        let mut pool: Vec<f32> = (0..15).map(|_| rng.gen_range(0.0..1.0)).collect();
+
+        vec![
+            (0.0, LinSrgb::new(pool.pop().unwrap(), pool.pop().unwrap(), pool.pop().unwrap())),
+            (0.1, LinSrgb::new(pool.pop().unwrap(), pool.pop().unwrap(), pool.pop().unwrap())),
+            (2.5, LinSrgb::new(pool.pop().unwrap(), pool.pop().unwrap(), pool.pop().unwrap())),
+            (6.0, LinSrgb::new(pool.pop().unwrap(), pool.pop().unwrap(), pool.pop().unwrap())),
+            (10.0, LinSrgb::new(pool.pop().unwrap(), pool.pop().unwrap(), pool.pop().unwrap())),
+        ]
+    }
+
+    pub fn rainbow_control_points() -> Vec<(f32, LinSrgb)> {
+        vec![
+            (0.0, LinSrgb::new(1.0, 0.0, 0.0)),
+            (0.05, LinSrgb::new(0.0, 1.0, 0.0)),
+            (0.5, LinSrgb::new(0.0, 0.0, 1.0)),
+            (1.5, LinSrgb::new(0.0, 1.0, 0.0)),
+            (2.5, LinSrgb::new(1.0, 0.0, 0.0)),
+        ]
+    }
+
+    fn component_color(component: Component) -> [u8; 4] {
+        match component {
+            Component::Exterior => [0, 0, 0, 0xff],
+            Component::MainCardioid => [0x4e, 0x79, 0xa7, 0xff],
+            Component::Period2Bulb => [0xf2, 0x8e, 0x2b, 0xff],
+            Component::OtherInterior => [0x59, 0xa1, 0x4f, 0xff],
+        }
+    }
+
+    /// Whether frames are computed on a background thread, see `set_background_computing`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn is_computing_in_background(&self) -> bool {
+        self.worker.is_some()
+    }
+
+    /// Moves computing to a background thread, or back. In the background the UI never waits on a frame: the last
+    /// finished one stays up until the next is done. Progressive preview passes are skipped while it's on.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_background_computing(&mut self, enabled: bool) {
+        if enabled != self.worker.is_some() {
+            self.worker = enabled.then(RenderWorker::spawn);
+            self.generator.recalculate();
+            self.redraw = true;
+        }
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Picks up whatever computing was left when paused, including anything navigation added since.
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn toggle_progressive(&mut self) {
+        self.progressive = !self.progressive;
+        self.progressive_level = 1;
+    }
+
+    /// Adds `samples` random trajectories to the Buddhabrot and draws its density into the frame, switching to it if
+    /// something else was shown. The density starts over whenever the view, formula or iteration cap changed since the
+    /// last call, so calling this repeatedly renders progressively.
+    pub fn render_buddhabrot(&mut self, samples: usize) {
+        self.nebulabrot = false;
+        self.render_density(samples);
+    }
+
+    /// Same as `render_buddhabrot` for the Nebulabrot, with a density layer per iteration cap in `caps` drawn as red,
+    /// green and blue. The caps are kept as `nebulabrot_caps`.
+    pub fn render_nebulabrot(&mut self, caps: [usize; 3], samples: usize) {
+        self.nebulabrot = true;
+        self.nebulabrot_caps = caps;
+        self.render_density(samples);
+    }
+
+    // The iteration caps of the density layers currently shown.
+    fn density_caps(&self) -> Vec<usize> {
+        if self.nebulabrot {
+            self.nebulabrot_caps.to_vec()
+        } else {
+            vec![self.generator.max_iterations() as usize]
+        }
+    }
+
+    fn render_density(&mut self, samples: usize) {
+        let caps = self.density_caps();
+        let buddhabrot = match self.buddhabrot.take() {
+            Some(buddhabrot) if buddhabrot.matches(&self.generator, &caps) => buddhabrot,
+            _ => Buddhabrot::new(&self.generator, &caps),
+        };
+        let buddhabrot = self.buddhabrot.insert(buddhabrot);
+
+        buddhabrot.accumulate(samples);
+        let (width, height) = buddhabrot.dimensions();
+        let mut buffer = vec![0u8; width * height * 4];
+        buddhabrot.draw(&mut buffer);
+        self.present(buffer);
+    }
+
+    // Whether the density shown still wants samples, either because it's short of the cap or because its view or caps
+    // went stale.
+    fn buddhabrot_pending(&self) -> bool {
+        match &self.buddhabrot {
+            Some(buddhabrot) => {
+                !buddhabrot.matches(&self.generator, &self.density_caps())
+                    || buddhabrot.samples() < MandelbrotRenderer::BUDDHABROT_MAX_SAMPLES
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_buddhabrot(&self) -> bool {
+        self.buddhabrot.is_some() && !self.nebulabrot
+    }
+
+    pub fn is_nebulabrot(&self) -> bool {
+        self.buddhabrot.is_some() && self.nebulabrot
+    }
+
+    /// Switches between the escape-time frame and the Buddhabrot, which starts accumulating from scratch.
+    pub fn toggle_buddhabrot(&mut self) {
+        let shown = self.is_buddhabrot();
+        self.show_density(!shown, false);
+    }
+
+    /// Switches between the escape-time frame and the Nebulabrot, like `toggle_buddhabrot`.
+    pub fn toggle_nebulabrot(&mut self) {
+        let shown = self.is_nebulabrot();
+        self.show_density(!shown, true);
+    }
+
+    fn show_density(&mut self, show: bool, nebulabrot: bool) {
+        self.nebulabrot = nebulabrot;
+        self.buddhabrot = show.then(|| Buddhabrot::new(&self.generator, &self.density_caps()));
+        #[cfg(not(target_arch = "wasm32"))]
+        if !show {
+            self.density_worker = None;
+        }
+        self.redraw = true;
+    }
+
+    pub fn toggle_components(&mut self) {
+        self.show_components = !self.show_components;
+        self.redraw = true;
+    }
+
+    pub fn is_dithering(&self) -> bool {
+        self.dither
+    }
+
+    /// Switches ordered dithering on or off, breaking up the banding 8-bit color leaves in smooth gradients.
+    pub fn toggle_dithering(&mut self) {
+        self.dither = !self.dither;
+        self.redraw = true;
+    }
+
+    // Palettes are interpolated in linear light, the frame wants sRGB encoded bytes.
+    fn color_to_rgba(color: &LinSrgb) -> [u8; 4] {
+        let color: Srgb<u8> = Srgb::from_linear(*color).into_format();
+        [color.red, color.green, color.blue, 0xff]
+    }
+
+    // Like `color_to_rgba`, but rounds at a threshold that depends on the pixel's place in the Bayer matrix rather
+    // than always at a half. Each channel ends up at most one step away from the undithered byte.
+    fn dithered_rgba(color: &LinSrgb, (x, y): (usize, usize)) -> [u8; 4] {
+        let color = Srgb::from_linear(*color);
+        let threshold = (MandelbrotRenderer::BAYER_MATRIX[y % 4][x % 4] as f32 + 0.5) / 16.0;
+        let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0 + threshold).floor().min(255.0) as u8;
+        [channel(color.red), channel(color.green), channel(color.blue), 0xff]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const WIDTH: usize = 64;
+    const HEIGHT: usize = 48;
+
+    // A generator over a square of the plane `range` across, widened to the render's aspect ratio.
+    fn view_at<F: Float + Send + Sync>(re: F, im: F, range: F) -> MandelbrotGenerator<F> {
+        let mut generator = MandelbrotGenerator::new(WIDTH, HEIGHT, 100.0);
+        let half = range / float(2.0);
+        generator.set_bounds(re - half, re + half, im - half, im + half);
+        generator
+    }
+
+    // Whether two coordinates agree to within a few ulps of the larger of the view's extent and its distance from the
+    // origin, the precision any view coordinate can be expected to hold.
+    fn close<F: Float>(a: F, b: F, scale: F) -> bool {
+        (a - b).abs() <= float::<F>(16.0) * F::epsilon() * scale
+    }
+
+    fn bounds_close<F: Float>(a: Bounds<F>, b: Bounds<F>, scale: F) -> bool {
+        close(a.x_min, b.x_min, scale)
+            && close(a.x_max, b.x_max, scale)
+            && close(a.y_min, b.y_min, scale)
+            && close(a.y_max, b.y_max, scale)
+    }
+
+    #[test]
+    fn normalize_maps_between_ranges_and_back() {
+        for n in [0.0, 13.0, 31.5, 64.0] {
+            let mapped = normalize(n, 0.0, 64.0, -2.0, 0.5);
+            assert!((normalize(mapped, -2.0, 0.5, 0.0, 64.0) - n).abs() < 1e-12);
+        }
+        assert_eq!(normalize(16.0, 0.0, 64.0, -2.0, 2.0), -1.0);
+    }
+
+    #[test]
+    fn normalize_maps_endpoints_to_endpoints() {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-15;
+        assert_eq!(normalize(0.0, 0.0, 640.0, -2.0, 0.47), -2.0);
+        assert!(close(normalize(640.0, 0.0, 640.0, -2.0, 0.47), 0.47));
+        // Reversed targets, like a y axis pointing down
+        assert_eq!(normalize(0.0, 0.0, 480.0, 1.12, -1.12), 1.12);
+        assert!(close(normalize(480.0, 0.0, 480.0, 1.12, -1.12), -1.12));
+    }
+
+    #[test]
+    fn normalize_extrapolates_outside_the_range() {
+        assert_eq!(normalize(-10.0, 0.0, 10.0, 0.0, 1.0), -1.0);
+        assert_eq!(normalize(20.0, 0.0, 10.0, 0.0, 1.0), 2.0);
+    }
+
+    #[test]
+    fn normalize_maps_an_empty_range_to_the_middle() {
+        assert_eq!(normalize(0.0, 0.0, 0.0, -2.0, 1.0), -0.5);
+        assert_eq!(normalize(5.0, 3.0, 3.0, -2.0, 1.0), -0.5);
+        let one = DoubleDouble::from_f64(1.0);
+        assert_eq!(normalize(one, one, one, -one, one), DoubleDouble::from_f64(0.0));
+    }
+
+    // Distinct escape counts in a frame, a frame of blocks has only a handful.
+    fn distinct_counts<F: Float + Send + Sync>(generator: &MandelbrotGenerator<F>) -> usize {
+        let mut counts: Vec<u32> = generator.iteration_counts.iter().map(|count| count.to_bits()).collect();
+        counts.sort_unstable();
+        counts.dedup();
+        counts.len()
+    }
+
+    #[test]
+    fn double_double_resolves_views_that_f64_renders_as_blocks() {
+        // Around the Misiurewicz point i, where the escape counts stay low however deep the view goes
+        let (re, im) = (DoubleDouble::from_f64(0.0), DoubleDouble::from_f64(1.0));
+        let magnification = 1e20;
+
+        let mut single = MandelbrotGenerator::<f64>::new(32, 24, 2000.0);
+        single.set_center_and_zoom(re.hi(), im.hi(), magnification);
+        assert!(single.precision_exhausted());
+        single.compute_all();
+
+        let mut double = MandelbrotGenerator::<DoubleDouble>::new(32, 24, 2000.0);
+        double.set_center_and_zoom(re, im, magnification);
+        assert!(!double.precision_exhausted());
+        double.compute_all();
+
+        assert!(distinct_counts(&single) <= 4, "{} distinct counts", distinct_counts(&single));
+        assert!(distinct_counts(&double) > 32 * 24 / 2, "{} distinct counts", distinct_counts(&double));
+    }
+
+    #[test]
+    fn periodicity_check_stops_early_inside_a_bulb() {
+        let mut generator = MandelbrotGenerator::<f64>::new(WIDTH, HEIGHT, 10_000.0);
+        generator.use_cardioid_check = false;
+
+        // Inside the period-3 bulb at the top of the cardioid, identified long before the cap runs out, where the
+        // bare loop would stop at the cap with the orbit still pending
+        let (value, pending) = generator.escape(-0.11, 0.74, 200.0, None);
+        assert_eq!(value.component, Component::OtherInterior);
+        assert!(pending.is_none());
+    }
+
+    #[test]
+    fn periodicity_check_lets_slow_escapes_through() {
+        let generator = MandelbrotGenerator::<f64>::new(WIDTH, HEIGHT, 10_000.0);
+        // Only exact repeats, which a slowly escaping orbit never makes
+        let mut unchecked = MandelbrotGenerator::<f64>::new(WIDTH, HEIGHT, 10_000.0);
+        unchecked.periodicity_epsilon = 0.0;
+
+        // In the neck between the cardioid and the period-2 bulb, lingering near the fixed point before escaping
+        let (value, _) = generator.escape(-0.75, 0.05, generator.max_iterations, None);
+        assert_eq!(value.component, Component::Exterior);
+        assert_eq!(value, unchecked.escape(-0.75, 0.05, generator.max_iterations, None).0);
+    }
+
+    #[test]
+    fn periodicity_check_starts_from_the_julia_seed() {
+        let mut generator = MandelbrotGenerator::<f64>::new(WIDTH, HEIGHT, 1000.0);
+        generator.set_fractal_type(FractalType::Julia);
+        generator.set_julia_c((0.5, 0.5));
+
+        // z0 = sqrt(-c), so z1 lands on the origin. The orbit of the origin escapes for this c
+        let r = 0.5f64.hypot(0.5);
+        let (x0, y0) = (((r - 0.5) / 2.0).sqrt(), -((r + 0.5) / 2.0).sqrt());
+        let (value, _) = generator.escape(x0, y0, generator.max_iterations, None);
+        assert_eq!(value.component, Component::Exterior);
+    }
+
+    #[test]
+    fn cardioid_check_leaves_the_default_view_unchanged() {
+        let mut checked = MandelbrotGenerator::<DoubleDouble>::new(WIDTH, HEIGHT, 200.0);
+        let mut unchecked = MandelbrotGenerator::<DoubleDouble>::new(WIDTH, HEIGHT, 200.0);
+        unchecked.use_cardioid_check = false;
+        checked.compute_all();
+        unchecked.compute_all();
+        assert_eq!(checked.iteration_counts, unchecked.iteration_counts);
+
+        let checked = MandelbrotRenderer::new(WIDTH, HEIGHT, checked);
+        let unchecked = MandelbrotRenderer::new(WIDTH, HEIGHT, unchecked);
+        assert_eq!(checked.render_to_image(WIDTH, HEIGHT), unchecked.render_to_image(WIDTH, HEIGHT));
+    }
+
+    #[test]
+    fn perturbed_pixels_follow_the_cardioid_check() {
+        // Deep inside the main cardioid, past where f64 tells the pixels apart
+        let mut generator = MandelbrotGenerator::<DoubleDouble>::new(8, 6, 100.0);
+        generator.set_center_and_zoom(DoubleDouble::from_f64(-0.1), DoubleDouble::from_f64(0.1), 1e14);
+        assert!(generator.uses_perturbation());
+
+        generator.compute_perturbed();
+        assert!(generator.components.iter().all(|&component| component == Component::MainCardioid));
+        let checked = generator.iteration_counts.clone();
+
+        generator.use_cardioid_check = false;
+        generator.compute_perturbed();
+        assert!(generator.components.iter().all(|&component| component == Component::OtherInterior));
+        assert_eq!(generator.iteration_counts, checked);
+    }
+
+    #[test]
+    fn resizing_keeps_the_center() {
+        let mut generator = view_at(-0.7455, 0.0985, 0.012);
+        let center = generator.center();
+        for (width, height) in [(640, 480), (33, 200), (0, 0), (0, 90), (1, 1), (1920, 1080), (WIDTH, HEIGHT)] {
+            generator.resize(width, height);
+            let (re, im) = generator.center();
+            assert!(close(re, center.0, 1.0) && close(im, center.1, 1.0), "{:?} moved to {:?}", center, (re, im));
+        }
+        assert_eq!(generator.dimensions(), (WIDTH, HEIGHT));
+        assert_eq!(generator.iteration_counts.len(), WIDTH * HEIGHT);
+    }
+
+    #[test]
+    fn zero_sized_resizes_are_ignored() {
+        let mut renderer = MandelbrotRenderer::new(WIDTH, HEIGHT, MandelbrotGenerator::new(WIDTH, HEIGHT, 100.0));
+        let bounds = renderer.generator.bounds();
+        renderer.resize(0, 0);
+        renderer.resize(WIDTH, 0);
+        assert_eq!(renderer.dimensions(), (WIDTH, HEIGHT));
+        assert_eq!(renderer.generator.bounds(), bounds);
+    }
+
+    #[test]
+    fn zoom_snap_lands_on_powers_of_the_magnification() {
+        let mut generator = MandelbrotGenerator::<f64>::new(WIDTH, HEIGHT, 100.0);
+        generator.zoom_snap = ZoomSnap::PowerOfTen;
+        let middle = (WIDTH as f32 / 2.0, HEIGHT as f32 / 2.0);
+        let magnifications: Vec<f64> = (0..3)
+            .map(|_| {
+                generator.zoom(middle, 0.5);
+                generator.magnification()
+            })
+            .collect();
+        for (magnification, expected) in magnifications.into_iter().zip([10.0, 100.0, 1000.0]) {
+            assert!((magnification / expected - 1.0).abs() < 1e-12, "{} instead of {}", magnification, expected);
+        }
+
+        generator.zoom_snap = ZoomSnap::PowerOfTwo;
+        generator.zoom(middle, 3.0);
+        assert!((generator.magnification() / 256.0 - 1.0).abs() < 1e-12, "{}", generator.magnification());
+    }
+
+    // The pixels of the current frame in row-major order.
+    fn frame<F: Float + Send + Sync>(generator: &MandelbrotGenerator<F>) -> Vec<PixelValue> {
+        let (width, height) = generator.dimensions();
+        (0..height).flat_map(|y| (0..width).map(move |x| generator.pixel(x, y))).collect()
+    }
+
+    // Runs every pixel through the full escape loop, leaving the frame complete.
+    fn compute_directly<F: Float + Send + Sync>(generator: &mut MandelbrotGenerator<F>) {
+        let (width, height) = generator.dimensions();
+        for (x, y) in (0..height).flat_map(|y| (0..width).map(move |x| (x, y))) {
+            let (value, _) = generator.test_pixel(x as f64, y as f64, generator.max_iterations, None);
+            generator.store(x, y, value);
+        }
+        generator.recalculate = false;
+    }
+
+    #[test]
+    fn panning_shifts_the_same_pixels_a_recompute_gives() {
+        let mut generator = view_at(-0.7455, 0.0985, 0.012);
+        compute_directly(&mut generator);
+
+        for (dx, dy) in [(5.0, -3.0), (-12.0, 0.0), (0.0, 7.0), (2.6, 1.4)] {
+            generator.pan(dx, dy);
+            assert!(generator.is_complete());
+            let shifted = frame(&generator);
+            compute_directly(&mut generator);
+
+            // Shifted bounds land pixel centers a few ulps from where they were, which only shows past the f32 counts
+            let close = |a: f64, b: f64| (a - b).abs() <= 1e-9 * a.abs().max(1.0);
+            for (shifted, direct) in shifted.iter().zip(frame(&generator)) {
+                assert_eq!((shifted.iterations, shifted.component), (direct.iterations, direct.component));
+                assert!(close(shifted.escape_angle, direct.escape_angle) && close(shifted.distance, direct.distance));
+                assert!(close(shifted.normal_angle, direct.normal_angle) && close(shifted.stripe, direct.stripe));
+            }
+        }
+    }
+
+    #[test]
+    fn accessors_report_the_view() {
+        let mut generator = MandelbrotGenerator::<DoubleDouble>::new(WIDTH, HEIGHT, 250.0);
+        let third = DoubleDouble::from_f64(1.0) / DoubleDouble::from_f64(3.0);
+        generator.set_bounds(-third, third, DoubleDouble::from_f64(-0.25), DoubleDouble::from_f64(0.25));
+
+        // Rounded to f64 from the double-double bounds
+        assert_eq!(generator.x_bounds(), (-1.0 / 3.0, 1.0 / 3.0));
+        assert_eq!(generator.y_bounds(), (-0.25, 0.25));
+        assert_eq!(generator.center(), (0.0, 0.0));
+        assert_eq!(generator.max_iterations(), 250.0);
+
+        generator.pan(WIDTH as f64 - 1.0, 0.0);
+        generator.set_max_iterations(1.0);
+        assert_eq!(generator.x_bounds(), (-1.0, -1.0 / 3.0));
+        assert_eq!(generator.center(), (-2.0 / 3.0, 0.0));
+        assert_eq!(generator.max_iterations(), MandelbrotGenerator::MIN_MAX_ITERATIONS);
+    }
+
+    #[test]
+    fn hybrid_precision_matches_f64() {
+        let mut hybrid = view_at(-0.7455, 0.0985, 0.012);
+        hybrid.set_max_iterations(300.0);
+        hybrid.hybrid_precision = true;
+        hybrid.compute_tiles(1);
+        assert!(hybrid.is_complete());
+
+        let mut direct = view_at(-0.7455, 0.0985, 0.012);
+        direct.set_max_iterations(300.0);
+        compute_directly(&mut direct);
+
+        // The f32 pass is only trusted away from the boundary, where a rounded orbit escapes at the same step
+        for (hybrid, direct) in hybrid.iteration_counts.iter().zip(&direct.iteration_counts) {
+            assert!((hybrid - direct).abs() < 1e-3, "{} instead of {}", hybrid, direct);
+        }
+    }
+
+    #[test]
+    fn flat_buffer_keeps_rows_in_order() {
+        // Not square, so swapped axes would show
+        let (width, height) = (7, 5);
+        let mut generator = view_at(-0.7455, 0.0985, 0.012);
+        generator.resize(width, height);
+        let cap = generator.max_iterations;
+        let rows: Vec<Vec<PixelValue>> = (0..height)
+            .map(|y| (0..width).map(|x| generator.test_pixel(x as f64, y as f64, cap, None).0).collect())
+            .collect();
+
+        let mut swept = Vec::new();
+        while !generator.is_complete() {
+            swept = generator.by_ref().take(width * height).collect();
+        }
+
+        assert_eq!(swept, rows.concat());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, value) in row.iter().enumerate() {
+                assert_eq!(generator.iteration_counts[y * width + x], value.iterations);
+                assert_eq!(generator.pixel(x, y), *value);
+            }
+        }
+    }
+
+    // The smooth escape count of c in plain f64 arithmetic, None if it's still bounded at the cap.
+    fn reference_count(cx: f64, cy: f64, cap: f64) -> Option<f64> {
+        let (mut x, mut y, mut iteration) = (0.0f64, 0.0f64, 0.0);
+        while x * x + y * y <= MandelbrotGenerator::DEFAULT_BAILOUT {
+            if iteration >= cap {
+                return None;
+            }
+            (x, y) = (x * x - y * y + cx, 2.0 * x * y + cy);
+            iteration += 1.0;
+        }
+        Some(iteration + 1.0 - (x * x + y * y).sqrt().log2().log2())
+    }
+
+    #[test]
+    fn single_precision_counts_keep_the_bands() {
+        let mut generator = view_at(-0.7455, 0.0985, 0.012);
+        generator.set_max_iterations(300.0);
+        compute_directly(&mut generator);
+
+        let mut escaped = 0;
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                let (cx, cy) = generator.pixel_position(x as f64, y as f64, generator.bounds());
+                if let Some(expected) = reference_count(cx, cy, generator.max_iterations) {
+                    let stored = generator.pixel(x, y).iterations;
+                    assert_eq!(stored.floor() as f64, expected.floor(), "pixel {:?}", (x, y));
+                    assert!((stored as f64 - expected).abs() < 1e-4, "{} instead of {}", stored, expected);
+                    escaped += 1;
+                }
+            }
+        }
+        assert!(escaped > WIDTH * HEIGHT / 2);
+    }
+
+    #[test]
+    fn returning_to_power_two_renders_the_same_frame() {
+        let mut original = view_at(-0.7455, 0.0985, 0.012);
+        original.compute_all();
+
+        let mut generator = view_at(-0.7455, 0.0985, 0.012);
+        generator.set_power(3);
+        generator.compute_all();
+        assert!(generator.iteration_counts != original.iteration_counts);
+        generator.set_power(2);
+        generator.compute_all();
+        assert_eq!(generator.iteration_counts, original.iteration_counts);
+    }
+
+    #[test]
+    fn largest_escaped_count_colors_without_panicking() {
+        let mut renderer = MandelbrotRenderer::new(WIDTH, HEIGHT, MandelbrotGenerator::new(WIDTH, HEIGHT, 200.0));
+        while !renderer.generator.is_complete() {
+            renderer.generator.compute_tiles(usize::MAX);
+        }
+
+        // The last f32 below the cap, anything from the cap up is interior
+        let cap = renderer.generator.max_iterations() as f32;
+        let value = PixelValue {
+            iterations: f32::from_bits(cap.to_bits() - 1),
+            escape_angle: std::f64::consts::PI,
+            distance: 0.0,
+            stripe: 1.0 - f64::EPSILON,
+            normal_angle: 0.0,
+            component: Component::Exterior,
+        };
+        let modes = [
+            ColoringMode::Linear,
+            ColoringMode::EscapeAngle { binary: false },
+            ColoringMode::EscapeAngle { binary: true },
+            ColoringMode::Histogram,
+            ColoringMode::DistanceEstimate,
+            ColoringMode::StripeAverage { frequency: 5.0 },
+            ColoringMode::NormalMap { light_angle: 45.0 },
+        ];
+        for mode in modes {
+            for scale in [PaletteScale::Linear, PaletteScale::Log, PaletteScale::Sqrt] {
+                renderer.set_coloring_mode(mode);
+                renderer.set_palette_scale(scale);
+                renderer.palette_offset = 1.0 - f32::EPSILON;
+                let rgba = renderer.color_pixel(&renderer.generator, value, (WIDTH - 1, HEIGHT - 1));
+                assert_eq!(rgba[3], 0xff);
+            }
+        }
+    }
+
+    #[test]
+    fn colors_are_gamma_encoded() {
+        assert_eq!(MandelbrotRenderer::color_to_rgba(&LinSrgb::new(0.5, 0.5, 0.5)), [188, 188, 188, 0xff]);
+        assert_eq!(MandelbrotRenderer::color_to_rgba(&LinSrgb::new(0.0, 0.0, 0.0)), [0, 0, 0, 0xff]);
+        assert_eq!(MandelbrotRenderer::color_to_rgba(&LinSrgb::new(1.0, 1.0, 1.0)), [255, 255, 255, 0xff]);
+    }
+
+    #[test]
+    fn smooth_counts_fall_continuously_across_bands() {
+        // Past the cusp at 1/4 the real axis is all exterior, and counts only fall moving away from the set. The
+        // renormalization is only seamless once c is small next to the escape radius, so use the largest preset
+        let mut generator = MandelbrotGenerator::<f64>::new(WIDTH, HEIGHT, 1000.0);
+        generator.set_bailout(65536.0);
+        let counts: Vec<f32> = (0..=2000)
+            .map(|step| 0.3 + 1.7 * step as f64 / 2000.0)
+            .map(|re| generator.escape(re, 0.0, generator.max_iterations, None).0.iterations)
+            .collect();
+
+        assert!(counts[0].floor() - counts[2000].floor() >= 10.0);
+        for pair in counts.windows(2) {
+            assert!(pair[1] <= pair[0], "count rises from {} to {}", pair[0], pair[1]);
+            assert!(pair[0] - pair[1] < 0.15, "count jumps from {} to {}", pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn cardioid_points_color_as_interior() {
+        let mut renderer = MandelbrotRenderer::new(WIDTH, HEIGHT, MandelbrotGenerator::new(WIDTH, HEIGHT, 500.0));
+        let mut values = Vec::new();
+        // The bare loop running into the cap, then the periodicity check and the cardioid test catching it sooner
+        renderer.generator.use_cardioid_check = false;
+        renderer.generator.periodicity_epsilon = 0.0;
+        values.push(renderer.generator.escape(-0.1, 0.05, renderer.generator.max_iterations, None).0);
+        renderer.generator.periodicity_epsilon = MandelbrotGenerator::DEFAULT_PERIODICITY_EPSILON;
+        values.push(renderer.generator.escape(-0.1, 0.05, renderer.generator.max_iterations, None).0);
+        renderer.generator.use_cardioid_check = true;
+        values.push(renderer.generator.escape(-0.1, 0.05, renderer.generator.max_iterations, None).0);
+
+        for value in values {
+            assert_eq!(value.iterations, 500.0);
+            for dither in [false, true] {
+                renderer.dither = dither;
+                assert_eq!(renderer.color_pixel(&renderer.generator, value, (1, 1)), [0, 0, 0, 0xff]);
+            }
+        }
+    }
+
+    #[test]
+    fn enforcing_aspect_makes_pixels_square() {
+        let views = [(-2.0, 0.47, -1.12, 1.12), (-0.75, -0.74, 0.1, 0.3), (0.0, 4.0, 0.0, 1.0)];
+        for (width, height) in [(640, 480), (480, 640), (1920, 1080), (100, 100), (1, 300)] {
+            let mut generator = MandelbrotGenerator::<f64>::new(width, height, 100.0);
+            for (x_min, x_max, y_min, y_max) in views {
+                generator.set_bounds(x_min, x_max, y_min, y_max);
+                let aspect = generator.x_range() / generator.y_range();
+                let expected = width as f64 / height as f64;
+                assert!((aspect / expected - 1.0).abs() < 1e-12, "{} instead of {}", aspect, expected);
+                // Widened, never cropped
+                assert!(generator.x_range() >= x_max - x_min && generator.y_range() >= y_max - y_min);
+            }
+        }
+    }
+
+    #[test]
+    fn lut_matches_the_interpolated_palette() {
+        let mut renderer = MandelbrotRenderer::new(WIDTH, HEIGHT, MandelbrotGenerator::new(WIDTH, HEIGHT, 100.0));
+        for kind in PaletteKind::ALL {
+            renderer.set_builtin_palette(kind);
+            for iterations in [0.5, 3.25, 17.8, 42.0, 99.9, 256.3, 511.75, 640.1, 993.4] {
+                let phase = renderer.palette_phase(iterations);
+                let direct = MandelbrotRenderer::color_to_rgba(&renderer.palette_color(iterations));
+                let lut = renderer.lut_rgba(phase, (0, 0));
+                for (direct, lut) in direct.into_iter().zip(lut) {
+                    assert!(direct.abs_diff(lut) <= 1, "{:?} at {}: {} against {}", kind, iterations, lut, direct);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn seeds_pick_different_first_colors() {
+        let first = |seed| MandelbrotRenderer::random_control_points(seed)[0];
+        assert_eq!(first(1), first(1));
+        assert_ne!(first(1).1, first(2).1);
+        assert_ne!(first(7).1, first(8).1);
+    }
+
+    #[test]
+    fn inverting_twice_restores_the_palette() {
+        let mut renderer = MandelbrotRenderer::new(WIDTH, HEIGHT, MandelbrotGenerator::new(WIDTH, HEIGHT, 100.0));
+        renderer.set_builtin_palette(PaletteKind::Fire);
+        let (palette, lut) = (renderer.palette.clone(), renderer.palette_lut.clone());
+
+        renderer.invert_palette();
+        assert_eq!(renderer.palette[0], palette[palette.len() - 1]);
+        assert_ne!(renderer.palette_lut, lut);
+
+        renderer.invert_palette();
+        assert_eq!(renderer.palette, palette);
+        assert_eq!(renderer.palette_lut, lut);
+    }
+
+    #[test]
+    fn halving_the_range_four_times_magnifies_sixteen_times() {
+        let mut generator = MandelbrotGenerator::<f64>::new(WIDTH, HEIGHT, 100.0);
+        assert_eq!(generator.magnification(), 1.0);
+        for _ in 0..4 {
+            generator.zoom((WIDTH as f32 / 3.0, HEIGHT as f32 / 4.0), 0.5);
+        }
+        assert!(close(generator.magnification(), 16.0, 16.0));
+
+        // Panning keeps the initial range to compare against
+        generator.pan(10.0, -7.0);
+        assert!(close(generator.magnification(), 16.0, 16.0));
+    }
+
+    #[test]
+    fn precision_runs_out_past_f64_depths() {
+        let at = |magnification| {
+            let mut generator = MandelbrotGenerator::<f64>::new(WIDTH, HEIGHT, 100.0);
+            generator.set_center_and_zoom(-0.75, 0.1, magnification);
+            generator.precision_exhausted()
+        };
+        assert!(!at(1.0));
+        assert!(!at(1e12));
+        assert!(at(1e16));
+
+        // Double-double has room to spare at the same depth
+        let mut generator = MandelbrotGenerator::<DoubleDouble>::new(WIDTH, HEIGHT, 100.0);
+        generator.set_center_and_zoom(DoubleDouble::from(-0.75), DoubleDouble::from(0.1), 1e16);
+        assert!(!generator.precision_exhausted());
+    }
+
+    #[test]
+    fn changing_the_iteration_cap_keeps_the_palette() {
+        let mut renderer = MandelbrotRenderer::new(WIDTH, HEIGHT, MandelbrotGenerator::new(WIDTH, HEIGHT, 100.0));
+        let mut frame = vec![0; WIDTH * HEIGHT * 4];
+        renderer.draw(&mut frame);
+        let palette = (renderer.palette.as_ptr(), renderer.palette_lut.as_ptr(), renderer.palette.clone());
+
+        for max_iterations in [37.0, 5000.0, 100.0] {
+            renderer.set_max_iterations(max_iterations);
+            renderer.draw(&mut frame);
+            assert_eq!((renderer.palette.as_ptr(), renderer.palette_lut.as_ptr()), (palette.0, palette.1));
+            assert_eq!(renderer.palette, palette.2);
+        }
+    }
+
+    #[test]
+    fn dithering_moves_channels_by_at_most_one() {
+        let renderer = MandelbrotRenderer::new(WIDTH, HEIGHT, MandelbrotGenerator::new(WIDTH, HEIGHT, 100.0));
+        assert!(!renderer.is_dithering());
+
+        let mut changed = false;
+        for step in 0..=200 {
+            let level = step as f32 / 200.0;
+            let color = LinSrgb::new(level, level * level, 1.0 - level);
+            let plain = MandelbrotRenderer::color_to_rgba(&color);
+            for position in (0..4).flat_map(|y| (0..4).map(move |x| (x, y))) {
+                let dithered = MandelbrotRenderer::dithered_rgba(&color, position);
+                assert_eq!(dithered, MandelbrotRenderer::dithered_rgba(&color, position));
+                for (plain, dithered) in plain.into_iter().zip(dithered) {
+                    let difference = plain.abs_diff(dithered);
+                    assert!(difference <= 1, "{:?} at {:?}: {} against {}", color, position, dithered, plain);
+                }
+                changed |= dithered != plain;
+            }
+        }
+        assert!(changed);
+    }
+
+    #[test]
+    fn julia_buddhabrot_traces_orbits_of_the_julia_parameter() {
+        // With c = 0 an orbit stays on its circle until it escapes, so nothing that escapes ever visits the unit disk
+        let inside_unit_disk = |renderer: &MandelbrotRenderer| {
+            let generator = &renderer.generator;
+            (0..WIDTH * HEIGHT).filter(|&index| {
+                let (re, im) = generator.pixel_to_complex(((index % WIDTH) as f32 + 0.5, (index / WIDTH) as f32 + 0.5));
+                re * re + im * im < 0.8 && renderer.frame_buffer[index * 4] > 0
+            }).count()
+        };
+
+        let origin = DoubleDouble::from(0.0);
+        let mut renderer = MandelbrotRenderer::new(WIDTH, HEIGHT, view_at(origin, origin, DoubleDouble::from(4.0)));
+        renderer.render_buddhabrot(20_000);
+        assert!(inside_unit_disk(&renderer) > 0);
+
+        renderer.generator.set_fractal_type(FractalType::Julia);
+        renderer.generator.set_julia_c((0.0, 0.0));
+        renderer.render_buddhabrot(20_000);
+        assert_eq!(inside_unit_disk(&renderer), 0);
+        assert!(renderer.frame_buffer.chunks_exact(4).any(|pixel| pixel[0] > 0));
+    }
+
+    #[test]
+    fn buddhabrot_accumulates_off_the_drawing_thread() {
+        let mut renderer = MandelbrotRenderer::new(WIDTH, HEIGHT, MandelbrotGenerator::new(WIDTH, HEIGHT, 100.0));
+        let mut frame = vec![0; WIDTH * HEIGHT * 4];
+        renderer.toggle_buddhabrot();
+        let samples = |renderer: &MandelbrotRenderer| renderer.buddhabrot.as_ref().map_or(0, |b| b.samples());
+        for _ in 0..1000 {
+            renderer.draw(&mut frame);
+            if samples(&renderer) > 0 {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(samples(&renderer) >= MandelbrotRenderer::BUDDHABROT_SAMPLES_PER_FRAME as u64);
+        assert!(frame.chunks_exact(4).any(|pixel| pixel[0] > 0));
+
+        renderer.toggle_buddhabrot();
+        assert!(renderer.density_worker.is_none());
+    }
+
+    #[test]
+    fn averaging_in_linear_light_brightens_mixed_pixels() {
+        let mut renderer = MandelbrotRenderer::new(WIDTH, HEIGHT, MandelbrotGenerator::new(WIDTH, HEIGHT, 100.0));
+        renderer.set_render_scale(2);
+        let (width, height) = renderer.generator.dimensions();
+        let checkerboard: Vec<u8> = (0..width * height)
+            .flat_map(|index| {
+                let value = if (index % width + index / width) % 2 == 0 { 0xff } else { 0 };
+                [value, value, value, 0xff]
+            })
+            .collect();
+
+        renderer.present(checkerboard);
+        assert!(renderer.frame_buffer.chunks_exact(4).all(|pixel| pixel == [128, 128, 128, 0xff]));
+        renderer.toggle_linear_averaging();
+        assert!(renderer.frame_buffer.chunks_exact(4).all(|pixel| pixel == [188, 188, 188, 0xff]));
+    }
+
+    #[test]
+    fn morphs_run_from_one_formula_to_the_other() {
+        let counts = |formula, morph| {
+            let mut generator = view_at(-0.5, 0.0, 3.0);
+            generator.set_formula(formula);
+            generator.set_morph(morph);
+            compute_directly(&mut generator);
+            frame(&generator).iter().map(|value| value.iterations).collect::<Vec<_>>()
+        };
+        let (mandelbrot, tricorn) = (counts(Formula::Mandelbrot, None), counts(Formula::Tricorn, None));
+
+        assert_eq!(counts(Formula::Tricorn, Some((Formula::Mandelbrot, 0.0))), mandelbrot);
+        assert_eq!(counts(Formula::Tricorn, Some((Formula::Mandelbrot, 1.0))), tricorn);
+        let halfway = counts(Formula::Tricorn, Some((Formula::Mandelbrot, 0.5)));
+        assert_ne!(halfway, mandelbrot);
+        assert_ne!(halfway, tricorn);
+    }
+
+    #[test]
+    fn cycling_formulas_morphs_while_animating() {
+        let mut renderer = MandelbrotRenderer::new(WIDTH, HEIGHT, MandelbrotGenerator::new(WIDTH, HEIGHT, 100.0));
+        renderer.cycle_formula();
+        assert_eq!((renderer.generator.formula(), renderer.generator.morph()), (Formula::Tricorn, None));
+
+        renderer.animate_zoom = true;
+        renderer.cycle_formula();
+        assert_eq!(renderer.generator.formula(), Formula::BurningShip);
+        assert_eq!(renderer.generator.morph(), Some((Formula::Tricorn, 0.0)));
+        assert!(renderer.is_busy());
+    }
+
+    proptest! {
+        #[test]
+        fn zooming_in_and_back_out_restores_the_bounds(
+            re in -2.0..2.0f64,
+            im in -2.0..2.0f64,
+            range in 1e-9..4.0f64,
+            factor in 0.01..0.99f64,
+        ) {
+            let mut generator = view_at(re, im, range);
+            let before = generator.bounds();
+            let middle = (WIDTH as f32 / 2.0, HEIGHT as f32 / 2.0);
+
+            generator.zoom(middle, factor);
+            generator.zoom(middle, 1.0 / factor);
+
+            // The zoomed in bounds are only held to the precision of the coordinates, zooming back out magnifies that
+            let scale = re.abs().max(im.abs()) / factor + generator.x_range();
+            prop_assert!(bounds_close(generator.bounds(), before, scale), "{:?} != {:?}", generator.bounds(), before);
+        }
+
+        #[test]
+        fn zooming_centers_on_the_clicked_point(
+            re in -2.0..2.0f64,
+            im in -2.0..2.0f64,
+            range in 1e-9..4.0f64,
+            factor in 0.01..100.0f64,
+            x in 0.0..WIDTH as f32,
+            y in 0.0..HEIGHT as f32,
+        ) {
+            let mut generator = view_at(re, im, range);
+            let clicked = generator.pixel_to_complex((x, y));
+            generator.zoom((x, y), factor);
+
+            let scale = re.abs().max(im.abs()) + range.max(generator.x_range());
+            let center = generator.center();
+            prop_assert!(close(center.0, clicked.0, scale) && close(center.1, clicked.1, scale));
+        }
+
+        // The quadratic fast path is the general power path with the products written out, to the bit
+        #[test]
+        fn power_two_step_matches_the_general_power(
+            x in -2.0..2.0f64,
+            y in -2.0..2.0f64,
+            cx in -2.0..2.0f64,
+            cy in -2.0..2.0f64,
+        ) {
+            let mut generator = MandelbrotGenerator::<f64>::new(WIDTH, HEIGHT, 100.0);
+            for formula in [Formula::Mandelbrot, Formula::Tricorn, Formula::BurningShip] {
+                generator.set_formula(formula);
+                let (zx, zy) = match formula {
+                    Formula::Mandelbrot => (x, y),
+                    Formula::Tricorn => (x, -y),
+                    Formula::BurningShip => (x.abs(), y.abs()),
+                };
+                let (px, py) = complex_pow(zx, zy, 2);
+                let fast = generator.step(x, y, x * x, y * y, cx, cy);
+                prop_assert_eq!((fast.0.to_bits(), fast.1.to_bits()), ((px + cx).to_bits(), (py + cy).to_bits()));
+            }
+        }
+
+        // Deep enough that f64 can't hold the view at all, with the center off the f64 grid
+        #[test]
+        fn zooming_holds_at_double_double_depths(
+            re in -2.0..2.0f64,
+            re_lo in -1e-17..1e-17f64,
+            im in -2.0..2.0f64,
+            range in 1e-26..1e-18f64,
+            factor in 0.01..0.99f64,
+            x in 0.0..WIDTH as f32,
+            y in 0.0..HEIGHT as f32,
+        ) {
+            let (re, im) = (DoubleDouble::new(re, re_lo), DoubleDouble::from_f64(im));
+            let mut generator = view_at(re, im, DoubleDouble::from_f64(range));
+            prop_assert!(!generator.precision_exhausted());
+            let before = generator.bounds();
+            let scale = re.abs().max(im.abs()) / DoubleDouble::from_f64(factor) + generator.x_range();
+            let middle = (WIDTH as f32 / 2.0, HEIGHT as f32 / 2.0);
+
+            generator.zoom(middle, factor);
+            generator.zoom(middle, 1.0 / factor);
+            prop_assert!(bounds_close(generator.bounds(), before, scale), "{:?} != {:?}", generator.bounds(), before);
+
+            let clicked = generator.pixel_to_complex_exact((x, y));
+            let scale = re.abs().max(im.abs()) + generator.x_range();
+            generator.zoom((x, y), factor);
+            let center = generator.precise_center();
+            prop_assert!(close(center.0, clicked.0, scale) && close(center.1, clicked.1, scale));
+        }
     }
 }
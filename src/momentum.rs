@@ -0,0 +1,88 @@
+// Keeps a dragged view gliding on after the mouse button is let go, slowing to a stop over a fraction of a second.
+
+use instant::{Duration, Instant};
+
+// Time for the glide to slow to about a third of its speed.
+const GLIDE_TIME_CONSTANT: f32 = 0.25;
+
+// Speed in pixels per second below which a glide stops, and a drag is let go without one.
+const MIN_GLIDE_SPEED: f32 = 30.0;
+
+// A drag held still for longer than this before the release was meant to stop there.
+const RELEASE_WINDOW: Duration = Duration::from_millis(60);
+
+// Weight of the newest movement in the drag velocity, so one jittery event doesn't decide the glide.
+const VELOCITY_SMOOTHING: f32 = 0.5;
+
+pub(crate) struct Momentum {
+    // Pixels per second, of the drag while it's held and of the glide after.
+    velocity: (f32, f32),
+    last_update: Instant,
+    gliding: bool,
+}
+
+impl Momentum {
+    pub(crate) fn new() -> Self {
+        Momentum {
+            velocity: (0.0, 0.0),
+            last_update: Instant::now(),
+            gliding: false,
+        }
+    }
+
+    pub(crate) fn is_gliding(&self) -> bool {
+        self.gliding
+    }
+
+    // A drag began, any glide still going ends.
+    pub(crate) fn start_drag(&mut self) {
+        self.velocity = (0.0, 0.0);
+        self.last_update = Instant::now();
+        self.gliding = false;
+    }
+
+    // The drag moved the view by `dx`, `dy` pixels since the last call.
+    pub(crate) fn track(&mut self, dx: f32, dy: f32) {
+        let now = Instant::now();
+        let dt = now.duration_since(self.last_update).as_secs_f32().max(0.001);
+        self.velocity = (
+            self.velocity.0 + (dx / dt - self.velocity.0) * VELOCITY_SMOOTHING,
+            self.velocity.1 + (dy / dt - self.velocity.1) * VELOCITY_SMOOTHING,
+        );
+        self.last_update = now;
+    }
+
+    // The drag ended, the view glides on if it was still moving.
+    pub(crate) fn release(&mut self) {
+        let moving = self.last_update.elapsed() <= RELEASE_WINDOW;
+        self.gliding = moving && speed(self.velocity) >= MIN_GLIDE_SPEED;
+        self.last_update = Instant::now();
+    }
+
+    pub(crate) fn stop(&mut self) {
+        self.gliding = false;
+    }
+
+    // The distance to pan since the last step while gliding, None once the glide is over.
+    pub(crate) fn step(&mut self) -> Option<(f32, f32)> {
+        if !self.gliding {
+            return None;
+        }
+
+        let now = Instant::now();
+        let dt = now.duration_since(self.last_update).as_secs_f32();
+        self.last_update = now;
+
+        let decay = (-dt / GLIDE_TIME_CONSTANT).exp();
+        self.velocity = (self.velocity.0 * decay, self.velocity.1 * decay);
+        if speed(self.velocity) < MIN_GLIDE_SPEED {
+            self.gliding = false;
+            return None;
+        }
+        Some((self.velocity.0 * dt, self.velocity.1 * dt))
+    }
+}
+
+fn speed(velocity: (f32, f32)) -> f32 {
+    velocity.0.hypot(velocity.1)
+}
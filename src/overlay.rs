@@ -0,0 +1,247 @@
+// Drawing helpers for things rendered on top of the fractal, straight into an RGBA frame.
+
+use visions_of_mandelbrot::MandelbrotRenderer;
+
+const GLYPH_WIDTH: usize = 5;
+const GLYPH_HEIGHT: usize = 7;
+// Gaps between characters and between lines, in pixels.
+const GLYPH_SPACING: usize = 1;
+const LINE_SPACING: usize = 3;
+// Space kept clear between the HUD text and the edge of its backdrop.
+const PANEL_PADDING: usize = 4;
+// Orbits are drawn as red dots joined by white lines.
+const ORBIT_LINE_COLOR: [u8; 4] = [0xff, 0xff, 0xff, 0xff];
+const ORBIT_DOT_COLOR: [u8; 4] = [0xff, 0x40, 0x40, 0xff];
+// Warnings under the HUD text stand out in orange.
+const HUD_WARNING_COLOR: [u8; 4] = [0xff, 0xa0, 0x20, 0xff];
+
+// A 5x7 bitmap font, one row per byte with the leftmost column in the highest of the five bits. Lowercase letters
+// are drawn as uppercase, anything else missing from the table is left blank.
+const FONT: [(char, [u8; GLYPH_HEIGHT]); 46] = [
+    ('0', [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110]),
+    ('1', [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110]),
+    ('2', [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111]),
+    ('3', [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110]),
+    ('4', [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010]),
+    ('5', [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110]),
+    ('6', [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110]),
+    ('7', [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000]),
+    ('8', [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110]),
+    ('9', [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100]),
+    ('A', [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001]),
+    ('B', [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110]),
+    ('C', [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110]),
+    ('D', [0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100]),
+    ('E', [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111]),
+    ('F', [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000]),
+    ('G', [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111]),
+    ('H', [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001]),
+    ('I', [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110]),
+    ('J', [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100]),
+    ('K', [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001]),
+    ('L', [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111]),
+    ('M', [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001]),
+    ('N', [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001]),
+    ('O', [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110]),
+    ('P', [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000]),
+    ('Q', [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101]),
+    ('R', [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001]),
+    ('S', [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110]),
+    ('T', [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100]),
+    ('U', [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110]),
+    ('V', [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100]),
+    ('W', [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010]),
+    ('X', [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001]),
+    ('Y', [0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100, 0b00100]),
+    ('Z', [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111]),
+    ('.', [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100]),
+    (',', [0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000]),
+    (':', [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000]),
+    ('-', [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000]),
+    ('+', [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000]),
+    ('=', [0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000]),
+    ('/', [0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000]),
+    ('(', [0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010]),
+    (')', [0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000]),
+    ('%', [0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011]),
+];
+
+fn set_pixel(frame: &mut [u8], width: usize, x: usize, y: usize, color: [u8; 4]) {
+    let offset = (y * width + x) * 4;
+    frame[offset..offset + 4].copy_from_slice(&color);
+}
+
+// Draws a one pixel wide rectangle outline between two opposite corners, clipped to the frame.
+pub(crate) fn draw_rectangle(frame: &mut [u8], width: usize, height: usize, a: (f32, f32), b: (f32, f32), color: [u8; 4]) {
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    let clamp_x = |x: f32| (x.max(0.0) as usize).min(width - 1);
+    let clamp_y = |y: f32| (y.max(0.0) as usize).min(height - 1);
+
+    let (left, right) = (clamp_x(a.0.min(b.0)), clamp_x(a.0.max(b.0)));
+    let (top, bottom) = (clamp_y(a.1.min(b.1)), clamp_y(a.1.max(b.1)));
+
+    for x in left..=right {
+        set_pixel(frame, width, x, top, color);
+        set_pixel(frame, width, x, bottom, color);
+    }
+    for y in top..=bottom {
+        set_pixel(frame, width, left, y, color);
+        set_pixel(frame, width, right, y, color);
+    }
+}
+
+// Draws a one pixel wide line between two points, clipped to the frame first so far away endpoints cost nothing.
+fn draw_line(frame: &mut [u8], width: usize, height: usize, a: (f64, f64), b: (f64, f64), color: [u8; 4]) {
+    // Liang-Barsky clipping of a + t(b - a) to the frame, narrowing t from [0, 1]
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let (mut t0, mut t1) = (0.0f64, 1.0f64);
+    let edges = [
+        (-dx, a.0),
+        (dx, width as f64 - 1.0 - a.0),
+        (-dy, a.1),
+        (dy, height as f64 - 1.0 - a.1),
+    ];
+    for (p, q) in edges {
+        if p == 0.0 {
+            if q < 0.0 {
+                return;
+            }
+        } else if p < 0.0 {
+            t0 = t0.max(q / p);
+        } else {
+            t1 = t1.min(q / p);
+        }
+    }
+    if t0 > t1 {
+        return;
+    }
+
+    let start = (a.0 + t0 * dx, a.1 + t0 * dy);
+    let end = (a.0 + t1 * dx, a.1 + t1 * dy);
+    let steps = (end.0 - start.0).abs().max((end.1 - start.1).abs()).ceil().max(1.0) as usize;
+    for step in 0..=steps {
+        let t = step as f64 / steps as f64;
+        let x = (start.0 + (end.0 - start.0) * t).round() as usize;
+        let y = (start.1 + (end.1 - start.1) * t).round() as usize;
+        set_pixel(frame, width, x.min(width - 1), y.min(height - 1), color);
+    }
+}
+
+// Draws an orbit as dots joined by lines, mapping each complex point through the current view. The starting point
+// is drawn larger so the direction of travel can be told apart.
+pub(crate) fn draw_orbit(frame: &mut [u8], width: usize, height: usize, renderer: &MandelbrotRenderer, orbit: &[(f64, f64)]) {
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    let points: Vec<(f64, f64)> = orbit.iter().map(|&z| renderer.complex_to_pixel(z)).collect();
+    for segment in points.windows(2) {
+        draw_line(frame, width, height, segment[0], segment[1], ORBIT_LINE_COLOR);
+    }
+
+    let inside = |&(_, &(x, y)): &(usize, &(f64, f64))| x >= 0.0 && y >= 0.0 && x < width as f64 && y < height as f64;
+    for (index, &(x, y)) in points.iter().enumerate().filter(inside) {
+        let radius = if index == 0 { 2.0 } else { 1.0 };
+        let (x, y) = (x as f32, y as f32);
+        draw_rectangle(frame, width, height, (x - radius, y - radius), (x + radius, y + radius), ORBIT_DOT_COLOR);
+    }
+}
+
+fn glyph(c: char) -> Option<&'static [u8; GLYPH_HEIGHT]> {
+    let c = c.to_ascii_uppercase();
+    FONT.iter().find(|(glyph, _)| *glyph == c).map(|(_, rows)| rows)
+}
+
+// Size in pixels of a block of text, lines separated by newlines.
+fn text_size(text: &str) -> (usize, usize) {
+    let lines = text.lines().count();
+    let columns = text.lines().map(|line| line.chars().count()).max().unwrap_or(0);
+    (
+        (columns * (GLYPH_WIDTH + GLYPH_SPACING)).saturating_sub(GLYPH_SPACING),
+        (lines * (GLYPH_HEIGHT + LINE_SPACING)).saturating_sub(LINE_SPACING),
+    )
+}
+
+// Draws text with its top left corner at (x, y), clipped to the frame.
+pub(crate) fn draw_text(frame: &mut [u8], width: usize, height: usize, x: usize, y: usize, text: &str, color: [u8; 4]) {
+    for (line_index, line) in text.lines().enumerate() {
+        let top = y + line_index * (GLYPH_HEIGHT + LINE_SPACING);
+        for (column, c) in line.chars().enumerate() {
+            let left = x + column * (GLYPH_WIDTH + GLYPH_SPACING);
+            let rows = match glyph(c) {
+                Some(rows) => rows,
+                None => continue,
+            };
+
+            for (row, bits) in rows.iter().enumerate() {
+                for col in 0..GLYPH_WIDTH {
+                    let (px, py) = (left + col, top + row);
+                    if bits & (1 << (GLYPH_WIDTH - 1 - col)) != 0 && px < width && py < height {
+                        set_pixel(frame, width, px, py, color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Halves the brightness of a rectangle so text drawn over it stays readable on any part of the fractal.
+fn darken_rectangle(frame: &mut [u8], width: usize, height: usize, x: usize, y: usize, w: usize, h: usize) {
+    for py in y..(y + h).min(height) {
+        for px in x..(x + w).min(width) {
+            let offset = (py * width + px) * 4;
+            for channel in &mut frame[offset..offset + 3] {
+                *channel /= 2;
+            }
+        }
+    }
+}
+
+// Draws a line being typed, with any message about it, in the bottom left corner.
+pub(crate) fn draw_prompt(frame: &mut [u8], width: usize, height: usize, text: &str) {
+    let (text_width, text_height) = text_size(text);
+    let (panel_width, panel_height) = (text_width + 2 * PANEL_PADDING, text_height + 2 * PANEL_PADDING);
+    let top = height.saturating_sub(panel_height);
+    darken_rectangle(frame, width, height, 0, top, panel_width, panel_height);
+    draw_text(frame, width, height, PANEL_PADDING, top + PANEL_PADDING, text, [0xff, 0xff, 0xff, 0xff]);
+}
+
+// Draws the current center, magnification over the initial view, iteration cap, progress of the pass being computed
+// and timings in the top left corner. The font has no multiplication sign, so the magnification ends in an X.
+pub(crate) fn draw_hud(frame: &mut [u8], width: usize, height: usize, renderer: &MandelbrotRenderer, fps: f64) {
+    let generator = &renderer.generator;
+    let (re, im) = generator.center();
+    let render_time = match renderer.last_render_time() {
+        Some(time) => format!("{:.1} MS", time.as_secs_f64() * 1000.0),
+        None => "-".to_string(),
+    };
+    let text = format!(
+        "RE   {:+.15}\nIM   {:+.15}\nZOOM {:.3e}X\nITER {}\nDONE {:.0}%\nDRAW {}\nFPS  {:.0}",
+        re,
+        im,
+        generator.magnification(),
+        generator.max_iterations(),
+        renderer.progress() * 100.0,
+        render_time,
+        fps
+    );
+
+    // Pixels past the generator's precision all land on a handful of points, worth saying why the image went blocky
+    let warning = generator.precision_exhausted().then_some("PRECISION LIMIT REACHED");
+
+    let (mut text_width, mut text_height) = text_size(&text);
+    let warning_y = PANEL_PADDING + text_height + LINE_SPACING;
+    if let Some(warning) = warning {
+        let (warning_width, warning_height) = text_size(warning);
+        text_width = text_width.max(warning_width);
+        text_height += LINE_SPACING + warning_height;
+    }
+    darken_rectangle(frame, width, height, 0, 0, text_width + 2 * PANEL_PADDING, text_height + 2 * PANEL_PADDING);
+    draw_text(frame, width, height, PANEL_PADDING, PANEL_PADDING, &text, [0xff, 0xff, 0xff, 0xff]);
+    if let Some(warning) = warning {
+        draw_text(frame, width, height, PANEL_PADDING, warning_y, warning, HUD_WARNING_COLOR);
+    }
+}
@@ -0,0 +1,36 @@
+// The view kept in the page URL's hash on the web build, so a link to the page opens the same place. The hash holds
+// the text `MandelbrotRenderer::location` writes, with commas in place of the spaces a URL would escape, which the
+// coordinates prompt reads back.
+
+use crate::prompt;
+use log::warn;
+use visions_of_mandelbrot::DoubleDouble;
+use wasm_bindgen::JsValue;
+
+// The center and magnification in the page's hash, None without one or if it isn't a location.
+pub(crate) fn read() -> Option<(DoubleDouble, DoubleDouble, f64)> {
+    let hash = web_sys::window()?.location().hash().ok()?;
+    let text = hash.strip_prefix('#').filter(|text| !text.is_empty())?;
+    match prompt::parse_coordinates(text) {
+        Ok(location) => Some(location),
+        Err(e) => {
+            warn!("ignoring URL hash {}: {}", hash, e.to_lowercase());
+            None
+        }
+    }
+}
+
+pub(crate) fn encode(location: &str) -> String {
+    format!("#{}", location.replace(' ', ","))
+}
+
+// Swaps the hash in place rather than setting it, which would add a step to the browser's back button for every view.
+pub(crate) fn write(hash: &str) {
+    let result = web_sys::window()
+        .ok_or_else(|| JsValue::from("couldn't access window"))
+        .and_then(|window| window.history())
+        .and_then(|history| history.replace_state_with_url(&JsValue::NULL, "", Some(hash)));
+    if let Err(e) = result {
+        warn!("couldn't update the URL hash: {:?}", e);
+    }
+}
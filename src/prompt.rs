@@ -0,0 +1,85 @@
+// A line of text typed into the window, for values that are entered rather than picked with the mouse.
+
+use num_traits::{Float, Num};
+use visions_of_mandelbrot::DoubleDouble;
+use winit_input_helper::TextChar;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum PromptKind {
+    // A seed for `MandelbrotRenderer::set_random_palette`.
+    PaletteSeed,
+    // The real and imaginary parts of a center and a magnification.
+    Coordinates,
+}
+
+pub(crate) struct Prompt {
+    pub(crate) kind: PromptKind,
+    pub(crate) text: String,
+    // Why the text was last rejected, shown until it's edited.
+    pub(crate) error: Option<String>,
+}
+
+impl Prompt {
+    pub(crate) fn new(kind: PromptKind) -> Self {
+        Prompt {
+            kind,
+            text: String::new(),
+            error: None,
+        }
+    }
+
+    // Takes the characters typed since the last event. Ones that can't be part of the value are dropped, which also
+    // keeps out anything the HUD font can't draw.
+    pub(crate) fn type_text(&mut self, typed: &[TextChar]) {
+        for c in typed {
+            match *c {
+                TextChar::Char(c) if self.accepts(c) => self.text.push(c),
+                TextChar::Back => {
+                    self.text.pop();
+                }
+                TextChar::Char(_) => continue,
+            }
+            self.error = None;
+        }
+    }
+
+    fn accepts(&self, c: char) -> bool {
+        match self.kind {
+            PromptKind::PaletteSeed => c.is_ascii_digit(),
+            PromptKind::Coordinates => c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E' | ' ' | ','),
+        }
+    }
+
+    // The prompt line with a cursor, and the last error below it.
+    pub(crate) fn display(&self) -> String {
+        let label = match self.kind {
+            PromptKind::PaletteSeed => "SEED",
+            PromptKind::Coordinates => "RE IM ZOOM",
+        };
+        match &self.error {
+            Some(error) => format!("{}: {}_\n{}", label, self.text, error),
+            None => format!("{}: {}_", label, self.text),
+        }
+    }
+}
+
+pub(crate) fn parse_seed(text: &str) -> Result<u64, String> {
+    text.trim().parse().map_err(|_| "NOT A SEED, EXPECTED A WHOLE NUMBER".to_string())
+}
+
+// Three numbers separated by spaces or commas. The center is parsed to full double-double precision so deep
+// locations survive being typed back in.
+pub(crate) fn parse_coordinates(text: &str) -> Result<(DoubleDouble, DoubleDouble, f64), String> {
+    let fields: Vec<&str> = text.split([' ', ',']).filter(|field| !field.is_empty()).collect();
+    let [re, im, zoom] = fields[..] else {
+        return Err(format!("EXPECTED 3 NUMBERS, GOT {}", fields.len()));
+    };
+
+    let part = |text: &str| DoubleDouble::from_str_radix(text, 10).ok().filter(|value| value.is_finite());
+    let re = part(re).ok_or_else(|| format!("BAD REAL PART {}", re))?;
+    let im = part(im).ok_or_else(|| format!("BAD IMAGINARY PART {}", im))?;
+    match zoom.parse::<f64>() {
+        Ok(zoom) if zoom.is_finite() && zoom > 0.0 => Ok((re, im, zoom)),
+        _ => Err(format!("BAD ZOOM {}, EXPECTED A POSITIVE NUMBER", zoom)),
+    }
+}
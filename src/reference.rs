@@ -0,0 +1,35 @@
+// Checking renders against reference images, shared by the `--compare` flag and the reference image tests.
+
+use image::ImageResult;
+use std::path::Path;
+
+/// Checks an RGBA frame against a reference PNG, pixels may differ by up to `tolerance` in each channel. Returns a
+/// description of the difference, or None if the frame matches.
+pub fn compare_png(
+    reference: &Path,
+    width: usize,
+    height: usize,
+    buf: &[u8],
+    tolerance: u8,
+) -> ImageResult<Option<String>> {
+    let expected = image::open(reference)?.into_rgba8();
+    if (expected.width() as usize, expected.height() as usize) != (width, height) {
+        return Ok(Some(format!(
+            "render is {}x{}, the reference {}x{}",
+            width,
+            height,
+            expected.width(),
+            expected.height()
+        )));
+    }
+
+    let differences: Vec<u8> = buf
+        .chunks_exact(4)
+        .zip(expected.as_raw().chunks_exact(4))
+        .map(|(actual, expected)| actual.iter().zip(expected).map(|(a, b)| a.abs_diff(*b)).max().unwrap_or(0))
+        .filter(|&difference| difference > tolerance)
+        .collect();
+    Ok(differences.iter().max().map(|largest| {
+        format!("{} of {} pixels differ, by up to {}", differences.len(), width * height, largest)
+    }))
+}
@@ -0,0 +1,137 @@
+// Touch navigation for the web build. Winit only sees touches as a single mouse pointer here, so they're kept away from
+// it and read from the canvas's touch events instead: one finger drags the view, two pinch it. Listeners collect the
+// movement as it happens and the event loop takes it once per frame, so however many touchmove events fire in between,
+// the view only moves and recomputes once.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{AddEventListenerOptions, HtmlCanvasElement, PointerEvent, TouchEvent};
+use winit::event_loop::EventLoopProxy;
+
+// Navigation gathered since the event loop last asked, positions in canvas pixels like the mouse's.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Gesture {
+    // A gesture began, its starting view belongs on the undo history.
+    pub(crate) started: bool,
+    pub(crate) pan: (f32, f32),
+    // Pinch midpoint and the factor the view's range scales by about it, below one for spreading fingers.
+    pub(crate) zoom: Option<((f32, f32), f64)>,
+}
+
+#[derive(Default)]
+struct TouchState {
+    // Fingers on the canvas by identifier, where they were at the last event.
+    points: Vec<(i32, (f32, f32))>,
+    pending: Gesture,
+}
+
+impl TouchState {
+    // Turns the fingers' new positions into movement. Only the fingers present both before and after count, so a
+    // finger landing or lifting just starts the next step from the new set.
+    fn update(&mut self, points: Vec<(i32, (f32, f32))>) {
+        let moved: Vec<((f32, f32), (f32, f32))> = points
+            .iter()
+            .filter_map(|(id, new)| self.points.iter().find(|(old_id, _)| old_id == id).map(|(_, old)| (*old, *new)))
+            .collect();
+
+        match moved[..] {
+            [(old, new)] if points.len() == 1 => {
+                self.pending.pan.0 += new.0 - old.0;
+                self.pending.pan.1 += new.1 - old.1;
+            }
+            [(old_a, new_a), (old_b, new_b)] => {
+                let midpoint = |a: (f32, f32), b: (f32, f32)| ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+                let distance = |a: (f32, f32), b: (f32, f32)| (a.0 - b.0).hypot(a.1 - b.1) as f64;
+                let (old_mid, new_mid) = (midpoint(old_a, old_b), midpoint(new_a, new_b));
+                self.pending.pan.0 += new_mid.0 - old_mid.0;
+                self.pending.pan.1 += new_mid.1 - old_mid.1;
+
+                let (old_distance, new_distance) = (distance(old_a, old_b), distance(new_a, new_b));
+                if old_distance > 0.0 && new_distance > 0.0 {
+                    let factor = self.pending.zoom.map_or(1.0, |(_, factor)| factor) * old_distance / new_distance;
+                    self.pending.zoom = Some((new_mid, factor));
+                }
+            }
+            _ => {}
+        }
+
+        if self.points.is_empty() && !points.is_empty() {
+            self.pending.started = true;
+        }
+        self.points = points;
+    }
+}
+
+pub(crate) struct TouchInput {
+    state: Rc<RefCell<TouchState>>,
+}
+
+impl TouchInput {
+    // Starts listening on the canvas. `proxy` wakes the event loop for each change, touches don't reach winit so it
+    // might otherwise sleep through a whole gesture.
+    pub(crate) fn attach(canvas: &HtmlCanvasElement, proxy: EventLoopProxy<()>) -> Result<TouchInput, JsValue> {
+        let state = Rc::new(RefCell::new(TouchState::default()));
+
+        let mut options = AddEventListenerOptions::new();
+        options.passive(false);
+        let listener_canvas = canvas.clone();
+        let listener_state = Rc::clone(&state);
+        let listener = Closure::wrap(Box::new(move |event: TouchEvent| {
+            // Keeps the browser from scrolling or zooming the page instead
+            event.prevent_default();
+            listener_state.borrow_mut().update(touch_points(&listener_canvas, &event));
+            let _ = proxy.send_event(());
+        }) as Box<dyn FnMut(_)>);
+        for name in ["touchstart", "touchmove", "touchend", "touchcancel"] {
+            canvas.add_event_listener_with_callback_and_add_event_listener_options(
+                name,
+                listener.as_ref().unchecked_ref(),
+                &options,
+            )?;
+        }
+        listener.forget();
+
+        // Winit listens for pointer events on the canvas itself, catching touch ones on the way down stops them there
+        let window = web_sys::window().ok_or("couldn't access window")?;
+        let mut capture = AddEventListenerOptions::new();
+        capture.capture(true);
+        let blocker = Closure::wrap(Box::new(move |event: PointerEvent| {
+            if event.pointer_type() == "touch" {
+                event.stop_propagation();
+            }
+        }) as Box<dyn FnMut(_)>);
+        for name in ["pointerdown", "pointermove", "pointerup", "pointercancel"] {
+            window.add_event_listener_with_callback_and_add_event_listener_options(
+                name,
+                blocker.as_ref().unchecked_ref(),
+                &capture,
+            )?;
+        }
+        blocker.forget();
+
+        Ok(TouchInput { state })
+    }
+
+    // The movement since the last call.
+    pub(crate) fn take(&self) -> Gesture {
+        std::mem::take(&mut self.state.borrow_mut().pending)
+    }
+}
+
+// The fingers still on the screen in canvas pixels. Touches come in CSS pixels relative to the page, the canvas is
+// sized in physical ones.
+fn touch_points(canvas: &HtmlCanvasElement, event: &TouchEvent) -> Vec<(i32, (f32, f32))> {
+    let rect = canvas.get_bounding_client_rect();
+    let scale = web_sys::window().map_or(1.0, |window| window.device_pixel_ratio());
+    let touches = event.touches();
+    (0..touches.length())
+        .filter_map(|index| touches.get(index))
+        .map(|touch| {
+            let x = (touch.client_x() as f64 - rect.left()) * scale;
+            let y = (touch.client_y() as f64 - rect.top()) * scale;
+            (touch.identifier(), (x as f32, y as f32))
+        })
+        .collect()
+}
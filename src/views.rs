@@ -0,0 +1,86 @@
+// Saving views, palettes and whole sessions to disk and reading them back, and raw iteration counts for other tools.
+
+use log::{error, info, warn};
+use std::error::Error;
+use std::fs;
+use std::io::BufWriter;
+use visions_of_mandelbrot::{DoubleDouble, MandelbrotGenerator, MandelbrotRenderer, Session, ViewParams};
+
+// Where the current view gets saved, relative to the working directory.
+pub(crate) const VIEW_FILE: &str = "view.json";
+pub(crate) const PALETTE_FILE: &str = "palette.json";
+pub(crate) const SESSION_FILE: &str = "session.json";
+pub(crate) const ITERATIONS_FILE: &str = "iterations.bin";
+
+fn write_view(path: &str, view: &ViewParams<DoubleDouble>) -> Result<(), Box<dyn Error>> {
+    fs::write(path, serde_json::to_string_pretty(view)?)?;
+    Ok(())
+}
+
+fn read_view(path: &str) -> Result<ViewParams<DoubleDouble>, Box<dyn Error>> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+// Failures are logged rather than fatal, a view that fails to save or load shouldn't take the viewer down.
+pub(crate) fn save_view(view: &ViewParams<DoubleDouble>) {
+    match write_view(VIEW_FILE, view) {
+        Ok(()) => info!("saved view to {}", VIEW_FILE),
+        Err(e) => error!("failed to save view to {}: {}", VIEW_FILE, e),
+    }
+}
+
+pub(crate) fn load_view() -> Option<ViewParams<DoubleDouble>> {
+    read_view(VIEW_FILE)
+        .map_err(|e| error!("failed to load view from {}: {}", VIEW_FILE, e))
+        .ok()
+}
+
+pub(crate) fn save_palette(renderer: &MandelbrotRenderer) {
+    match fs::write(PALETTE_FILE, renderer.export_palette()) {
+        Ok(()) => info!("saved palette to {}", PALETTE_FILE),
+        Err(e) => error!("failed to save palette to {}: {}", PALETTE_FILE, e),
+    }
+}
+
+pub(crate) fn load_palette(renderer: &mut MandelbrotRenderer) {
+    let result = fs::read_to_string(PALETTE_FILE)
+        .map_err(|e| e.to_string())
+        .and_then(|json| renderer.import_palette(&json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        error!("failed to load palette from {}: {}", PALETTE_FILE, e);
+    }
+}
+
+pub(crate) fn save_session(renderer: &MandelbrotRenderer) {
+    let result = serde_json::to_string_pretty(&renderer.to_session())
+        .map_err(|e| e.to_string())
+        .and_then(|json| fs::write(SESSION_FILE, json).map_err(|e| e.to_string()));
+    match result {
+        Ok(()) => info!("saved session to {}", SESSION_FILE),
+        Err(e) => error!("failed to save session to {}: {}", SESSION_FILE, e),
+    }
+}
+
+pub(crate) fn load_session(renderer: &mut MandelbrotRenderer) {
+    let result = fs::read_to_string(SESSION_FILE)
+        .map_err(|e| e.to_string())
+        .and_then(|json| serde_json::from_str::<Session>(&json).map_err(|e| e.to_string()));
+    match result {
+        Ok(session) => {
+            renderer.restore_session(&session);
+            info!("loaded session from {}", SESSION_FILE);
+        }
+        Err(e) => error!("failed to load session from {}: {}", SESSION_FILE, e),
+    }
+}
+
+pub(crate) fn save_iterations(generator: &MandelbrotGenerator<DoubleDouble>) {
+    if !generator.is_complete() {
+        warn!("the frame is still computing, saving the counts of the current pass");
+    }
+    let result = fs::File::create(ITERATIONS_FILE).and_then(|file| generator.export_iterations(BufWriter::new(file)));
+    match result {
+        Ok(()) => info!("saved iteration counts to {}", ITERATIONS_FILE),
+        Err(e) => error!("failed to save iteration counts to {}: {}", ITERATIONS_FILE, e),
+    }
+}
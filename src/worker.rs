@@ -0,0 +1,185 @@
+use crate::buddhabrot::Buddhabrot;
+use crate::double_double::DoubleDouble;
+use crate::mandelbrot::MandelbrotGenerator;
+use instant::{Duration, Instant};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+// Computes frames on a thread of its own so the UI never waits on the escape loop. Views go over as generator copies
+// made by `at_resolution`, which carry the view along with every setting that affects the picture, and come back
+// fully computed.
+pub(crate) struct RenderWorker {
+    // Dropped first on shutdown, which ends the thread's receive loop.
+    requests: Option<Sender<MandelbrotGenerator<DoubleDouble>>>,
+    frames: Receiver<MandelbrotGenerator<DoubleDouble>>,
+    thread: Option<JoinHandle<()>>,
+    // Revision of the last generator handed over, and when.
+    requested: Option<(u64, Instant)>,
+    // Progress of the frame in the works as the bits of an f64, see `MandelbrotGenerator::progress`.
+    progress: Arc<AtomicU64>,
+}
+
+impl RenderWorker {
+    pub(crate) fn spawn() -> Self {
+        let (requests, inbox) = mpsc::channel();
+        let (outbox, frames) = mpsc::channel();
+        let progress = Arc::new(AtomicU64::new(0));
+        let shared = Arc::clone(&progress);
+        let thread = std::thread::Builder::new()
+            .name("render worker".to_string())
+            .spawn(move || RenderWorker::run(inbox, outbox, &shared))
+            .expect("spawning the render worker failed");
+
+        RenderWorker {
+            requests: Some(requests),
+            frames,
+            thread: Some(thread),
+            requested: None,
+            progress,
+        }
+    }
+
+    // The thread's loop. Only the newest request is worth finishing, so anything queued behind it is skipped and a
+    // frame in progress is abandoned as soon as a new one arrives, checked after every batch of tiles.
+    fn run(
+        inbox: Receiver<MandelbrotGenerator<DoubleDouble>>,
+        outbox: Sender<MandelbrotGenerator<DoubleDouble>>,
+        progress: &AtomicU64,
+    ) {
+        #[cfg(feature = "parallel")]
+        let batch = rayon::current_num_threads();
+        #[cfg(not(feature = "parallel"))]
+        let batch = 1;
+
+        let mut next = inbox.recv().ok();
+        while let Some(mut generator) = next.take() {
+            while !generator.is_complete() {
+                generator.compute_tiles(batch);
+                progress.store(generator.progress().to_bits(), Ordering::Relaxed);
+
+                match inbox.try_recv() {
+                    Ok(newer) => {
+                        next = Some(inbox.try_iter().last().unwrap_or(newer));
+                        break;
+                    }
+                    Err(TryRecvError::Disconnected) => return,
+                    Err(TryRecvError::Empty) => {}
+                }
+            }
+
+            if next.is_none() {
+                if outbox.send(generator).is_err() {
+                    return;
+                }
+                next = inbox.recv().ok();
+            }
+        }
+    }
+
+    // Hands a view over, replacing whatever the thread was working on.
+    pub(crate) fn request(&mut self, generator: MandelbrotGenerator<DoubleDouble>) {
+        self.requested = Some((generator.revision(), Instant::now()));
+        self.progress.store(0, Ordering::Relaxed);
+        if let Some(requests) = &self.requests {
+            // Only fails if the thread is gone, in which case there's nobody to compute it anyway
+            let _ = requests.send(generator);
+        }
+    }
+
+    pub(crate) fn progress(&self) -> f64 {
+        f64::from_bits(self.progress.load(Ordering::Relaxed))
+    }
+
+    pub(crate) fn requested_revision(&self) -> Option<u64> {
+        self.requested.map(|(revision, _)| revision)
+    }
+
+    // Time since the last request was handed over.
+    pub(crate) fn elapsed(&self) -> Option<Duration> {
+        self.requested.map(|(_, started)| started.elapsed())
+    }
+
+    // The newest frame the thread finished since the last call, if any.
+    pub(crate) fn latest(&self) -> Option<MandelbrotGenerator<DoubleDouble>> {
+        self.frames.try_iter().last()
+    }
+}
+
+impl Drop for RenderWorker {
+    fn drop(&mut self) {
+        self.requests = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// Accumulates the Buddhabrot on a thread of its own, a batch of samples per request. The density goes over and comes
+// back with the batch added and drawn into an RGBA frame, so neither the sampling nor the drawing holds up the UI.
+pub(crate) struct DensityWorker {
+    // Dropped first on shutdown, which ends the thread's receive loop.
+    requests: Option<Sender<Buddhabrot>>,
+    batches: Receiver<(Buddhabrot, Vec<u8>)>,
+    thread: Option<JoinHandle<()>>,
+    // A density was handed over and hasn't come back yet.
+    busy: bool,
+}
+
+impl DensityWorker {
+    pub(crate) fn spawn(samples: usize) -> Self {
+        let (requests, inbox) = mpsc::channel::<Buddhabrot>();
+        let (outbox, batches) = mpsc::channel();
+        let thread = std::thread::Builder::new()
+            .name("density worker".to_string())
+            .spawn(move || {
+                for mut buddhabrot in inbox {
+                    buddhabrot.accumulate(samples);
+                    let (width, height) = buddhabrot.dimensions();
+                    let mut frame = vec![0; width * height * 4];
+                    buddhabrot.draw(&mut frame);
+                    if outbox.send((buddhabrot, frame)).is_err() {
+                        return;
+                    }
+                }
+            })
+            .expect("spawning the density worker failed");
+
+        DensityWorker {
+            requests: Some(requests),
+            batches,
+            thread: Some(thread),
+            busy: false,
+        }
+    }
+
+    pub(crate) fn is_busy(&self) -> bool {
+        self.busy
+    }
+
+    // Hands a density over for another batch of samples.
+    pub(crate) fn request(&mut self, buddhabrot: Buddhabrot) {
+        if let Some(requests) = &self.requests {
+            self.busy = requests.send(buddhabrot).is_ok();
+        }
+    }
+
+    // The density and its frame once the batch is done, if it is.
+    pub(crate) fn latest(&mut self) -> Option<(Buddhabrot, Vec<u8>)> {
+        let batch = self.batches.try_iter().last();
+        if batch.is_some() {
+            self.busy = false;
+        }
+        batch
+    }
+}
+
+impl Drop for DensityWorker {
+    fn drop(&mut self) {
+        self.requests = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
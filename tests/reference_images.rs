@@ -0,0 +1,51 @@
+// Renders a few fixed views and palettes through the library and checks them against the images in
+// `images/reference`, to catch changes to the rendering by accident. After a deliberate change, run with
+// UPDATE_REFERENCE_IMAGES set to write the new renders over the references.
+
+use std::path::PathBuf;
+use visions_of_mandelbrot::{compare_png, DoubleDouble, MandelbrotGenerator, MandelbrotRenderer, PaletteKind};
+
+const WIDTH: usize = 160;
+const HEIGHT: usize = 120;
+
+// Largest difference in any color channel of a pixel that still matches, room for float differences between
+// platforms and builds.
+const TOLERANCE: u8 = 2;
+
+// Renders a view given as (x_min, y_min, x_max, y_max), or the default one, and compares it with the reference of the
+// same name.
+fn check_reference(name: &str, palette: PaletteKind, max_iterations: f64, bounds: Option<(f64, f64, f64, f64)>) {
+    let mut generator = MandelbrotGenerator::<DoubleDouble>::new(WIDTH, HEIGHT, max_iterations);
+    if let Some((x_min, y_min, x_max, y_max)) = bounds {
+        generator.set_bounds(x_min.into(), x_max.into(), y_min.into(), y_max.into());
+    }
+    let mut renderer = MandelbrotRenderer::new(WIDTH, HEIGHT, generator);
+    renderer.set_builtin_palette(palette);
+    let rgba = renderer.render_to_image(WIDTH, HEIGHT);
+
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "images", "reference", &format!("{}.png", name)].iter().collect();
+    if std::env::var_os("UPDATE_REFERENCE_IMAGES").is_some() {
+        image::save_buffer(&path, &rgba, WIDTH as u32, HEIGHT as u32, image::ColorType::Rgba8).unwrap();
+        return;
+    }
+
+    let difference = compare_png(&path, WIDTH, HEIGHT, &rgba, TOLERANCE).unwrap();
+    assert!(difference.is_none(), "{}: {}", path.display(), difference.unwrap_or_default());
+}
+
+#[test]
+fn default_view() {
+    check_reference("default", PaletteKind::Rainbow, MandelbrotGenerator::DEFAULT_MAX_ITERATIONS, None);
+}
+
+#[test]
+fn seahorse_valley() {
+    let bounds = (-0.7535, 0.0925, -0.7375, 0.1045);
+    check_reference("seahorse_valley", PaletteKind::Fire, MandelbrotGenerator::DEFAULT_MAX_ITERATIONS, Some(bounds));
+}
+
+#[test]
+fn deep_zoom() {
+    let bounds = (-0.743643887062151, 0.131825904186580, -0.743643887012151, 0.131825904224080);
+    check_reference("deep_zoom", PaletteKind::Ocean, 3000.0, Some(bounds));
+}